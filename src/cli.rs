@@ -0,0 +1,77 @@
+use clap::{Parser, Subcommand};
+use regex::Regex;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{parse_duration, RawConfig};
+use crate::gewe_api::ContactKind;
+
+/// 命令行入口。不带子命令时作为 MCP 服务器通过 stdio 运行。
+#[derive(Debug, Parser)]
+#[clap(
+    name = "gewe-notice-mcp",
+    about = "一个通过微信机器人发送AI任务状态通知的轻量级工具",
+    version
+)]
+pub struct Cli {
+    #[clap(flatten)]
+    pub config: RawConfig,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 校验配置并端到端探测 Gewe API，输出检查报告；有检查失败时以非零状态退出
+    Doctor,
+    /// 向默认接收者发送一条文本通知后退出，供脚本与 CI 使用；失败时退出码 75 表示可重试，69 表示需人工处理，78 表示配置错误
+    Send {
+        /// 消息内容，为 `-` 时从标准输入读取
+        message: String,
+    },
+    /// 运行命令并原样转发其输出，结束后发送包含命令、退出状态、耗时、主机名与最后输出的通知；以命令自身的退出码退出
+    Exec {
+        /// 要执行的命令及其参数，建议写在 `--` 之后
+        #[clap(
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "COMMAND"
+        )]
+        command: Vec<String>,
+    },
+    /// 列出机器人账号的好友与群聊，显示昵称、备注与 wxid，用于查找接收者的 wxid
+    Contacts {
+        /// 在 wxid、昵称与备注中查找的关键字，不区分大小写
+        query: Option<String>,
+        /// 只列出一种联系人: friend 或 chatroom
+        #[clap(long)]
+        kind: Option<ContactKind>,
+        /// 以 JSON 格式输出
+        #[clap(long)]
+        json: bool,
+    },
+    /// 列出群成员的 wxid、昵称、群昵称以及群主/管理员身份，用于填写 at_list
+    Members {
+        /// 群聊 ID，如 12345678@chatroom，可省略 @chatroom 后缀
+        chatroom: String,
+        /// 以 JSON 格式输出
+        #[clap(long)]
+        json: bool,
+    },
+    /// 像 tail -F 一样跟随日志文件 (支持日志轮转)，出现匹配的行时发送附带上下文的通知
+    Watch {
+        /// 要监视的日志文件，从当前末尾开始读取
+        file: PathBuf,
+        /// 触发通知的正则表达式，可重复指定
+        #[clap(long = "pattern", short = 'p', required = true)]
+        patterns: Vec<Regex>,
+        /// 通知中附带的匹配行前后的行数
+        #[clap(long, short = 'C', default_value_t = 3)]
+        context: usize,
+        /// 同一模式两次通知的最小间隔，期间的匹配只计数
+        #[clap(long, default_value = "5m", value_parser = parse_duration)]
+        cooldown: Duration,
+    },
+}
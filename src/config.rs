@@ -1,8 +1,112 @@
-use crate::errors::{ConfigValidationError, Result, TokenValidationError, WxIdValidationError};
+use crate::config_file::{FileConfig, RecipientProfile, RouteProfile};
+use crate::errors::{
+    error_chain, ConfigValidationError, GeweNoticeError, Result, TokenValidationError,
+    WxIdValidationError,
+};
+use crate::exec::{ExecSettings, Template};
+use crate::quiet_hours::{QuietHours, QuietMode, QuietTimezone, QuietWindow};
+use crate::routing::{RouteRule, Severity, DEFAULT_ROUTE_TARGET};
 use clap::Parser;
+use std::collections::BTreeMap;
 use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
 use uuid::Uuid;
 
+/// 未配置 `base_url` 时使用的 Gewe 官方地址。
+pub const DEFAULT_BASE_URL: &str = "https://www.geweapi.com";
+
+/// 单条文本消息的默认长度上限 (字符数)，超过后自动分段发送。
+pub const DEFAULT_MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// 允许配置的最小长度上限，需为分段序号留出空间。
+pub const MIN_MAX_MESSAGE_LENGTH: usize = 50;
+
+/// 允许配置的最大尝试次数 (含首次请求)。
+pub const MAX_ATTEMPTS_LIMIT: u32 = 10;
+
+/// 失败请求的重试策略：指数退避并加入随机抖动。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// 最大尝试次数，包含首次请求。
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.0,
+        }
+    }
+}
+
+/// 访问 Gewe API 的超时、重试与并发设置。
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpSettings {
+    /// 单次 HTTP 请求的超时时间。
+    pub request_timeout: Duration,
+    /// 一次发送 (含全部重试) 的总超时时间。
+    pub send_timeout: Duration,
+    pub retry: RetryPolicy,
+    /// 同时进行的 Gewe API 请求数上限，仅在启动时生效。
+    pub max_concurrency: usize,
+    /// 切换到备用地址后，探测主地址是否恢复的间隔。
+    pub failback_interval: Duration,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(10),
+            send_timeout: Duration::from_secs(30),
+            retry: RetryPolicy::default(),
+            max_concurrency: 10,
+            failback_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// 访问 Gewe API 的代理与 TLS 设置，仅在启动时生效。
+#[derive(Debug, Clone, Default)]
+pub struct TransportSettings {
+    /// 代理地址。未设置时沿用 `HTTPS_PROXY` / `HTTP_PROXY` / `NO_PROXY` 环境变量。
+    pub proxy: Option<String>,
+    /// 在内置根证书之外额外信任的 CA 证书。
+    pub ca_certs: Vec<reqwest::Certificate>,
+    /// 双向 TLS 使用的客户端证书与私钥。
+    pub client_identity: Option<reqwest::Identity>,
+    /// 不校验服务器证书，仅用于自签名证书的测试环境。
+    pub insecure: bool,
+}
+
+/// 解析 `500ms`、`10s`、`2m` 形式的时长，不带单位时按秒计算。
+pub fn parse_duration(value: &str) -> std::result::Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| "应为数字加单位，例如 500ms、10s、2m".to_string())?;
+
+    match unit.trim() {
+        "ms" => Ok(Duration::from_millis(number)),
+        "" | "s" => Ok(Duration::from_secs(number)),
+        "m" => number
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("{} 分钟超出可表示的范围", number)),
+        other => Err(format!("未知的时间单位 '{}'，可用 ms、s、m", other)),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ValidatedToken(Uuid);
 
@@ -96,67 +200,648 @@ impl fmt::Display for WxId {
 }
 
 #[derive(Debug, Clone, Parser)]
-#[clap(
-    name = "gewe-notice-mcp",
-    about = "一个通过微信机器人发送AI任务状态通知的轻量级工具",
-    version
-)]
 pub struct RawConfig {
     #[clap(
         long,
+        global = true,
+        env = "GEWE_NOTICE_CONFIG",
+        help = "TOML 配置文件路径 (优先级低于命令行参数与环境变量)"
+    )]
+    pub config: Option<PathBuf>,
+
+    #[clap(
+        long,
+        global = true,
         env = "GEWE_NOTICE_BASE_URL",
-        default_value = "https://www.geweapi.com",
-        help = "Gewe API 基础URL"
+        value_delimiter = ',',
+        help = "Gewe API 基础URL，多个地址以逗号分隔时按顺序故障切换 [默认: https://www.geweapi.com]"
     )]
-    pub base_url: String,
+    pub base_url: Option<Vec<String>>,
 
-    #[clap(long, env = "GEWE_NOTICE_TOKEN", help = "Gewe API Token (UUID格式)")]
-    pub token: String,
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_TOKEN",
+        help = "Gewe API Token (UUID格式)"
+    )]
+    pub token: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_TOKEN_FILE",
+        help = "从文件读取 Gewe API Token，例如 secrets 挂载路径"
+    )]
+    pub token_file: Option<PathBuf>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_TOKEN_COMMAND",
+        help = "执行命令并以其标准输出作为 Gewe API Token，例如密码管理器 CLI"
+    )]
+    pub token_command: Option<String>,
 
-    #[clap(long, env = "GEWE_NOTICE_APP_ID", help = "微信机器人App ID (wx_开头)")]
-    pub app_id: String,
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_APP_ID",
+        help = "微信机器人App ID (wx_开头)"
+    )]
+    pub app_id: Option<String>,
 
-    #[clap(long, env = "GEWE_NOTICE_WXID", help = "接收者WXID (个人或群聊)")]
-    pub wxid: String,
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_WXID",
+        help = "接收者WXID (个人或群聊)"
+    )]
+    pub wxid: Option<String>,
 
     #[clap(
         long,
+        global = true,
         env = "GEWE_NOTICE_AT_LIST",
         value_delimiter = ',',
         help = "@列表 (逗号分隔的wxid或'all')"
     )]
     pub at_list: Option<Vec<String>>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_ALLOWED_RECIPIENTS",
+        value_delimiter = ',',
+        help = "允许按次覆盖的接收者WXID (逗号分隔)"
+    )]
+    pub allowed_recipients: Option<Vec<String>>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_MAX_LENGTH",
+        help = "单条文本消息的长度上限，超出后自动分段发送 [默认: 2000]"
+    )]
+    pub max_message_length: Option<usize>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_REQUEST_TIMEOUT",
+        help = "单次 HTTP 请求的超时时间，例如 10s、500ms [默认: 10s]"
+    )]
+    pub request_timeout: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_SEND_TIMEOUT",
+        help = "一次发送 (含全部重试) 的总超时时间 [默认: 30s]"
+    )]
+    pub send_timeout: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_MAX_ATTEMPTS",
+        help = "临时故障时的最大尝试次数，包含首次请求 [默认: 3]"
+    )]
+    pub max_attempts: Option<u32>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_RETRY_INITIAL_DELAY",
+        help = "首次重试前的等待时间 [默认: 100ms]"
+    )]
+    pub retry_initial_delay: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_RETRY_MAX_DELAY",
+        help = "重试等待时间的上限 [默认: 5s]"
+    )]
+    pub retry_max_delay: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_RETRY_MULTIPLIER",
+        help = "每次重试等待时间的增长倍数 [默认: 2]"
+    )]
+    pub retry_multiplier: Option<f64>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_MAX_CONCURRENCY",
+        help = "同时进行的 Gewe API 请求数上限 [默认: 10]"
+    )]
+    pub max_concurrency: Option<usize>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_FAILBACK_INTERVAL",
+        help = "切换到备用地址后探测主地址是否恢复的间隔 [默认: 60s]"
+    )]
+    pub failback_interval: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_PROXY",
+        help = "访问 Gewe API 使用的代理，例如 http://proxy.corp:3128 [默认: 读取 HTTPS_PROXY 等环境变量]"
+    )]
+    pub proxy: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_CA_CERT",
+        help = "额外信任的 CA 证书 (PEM，可包含多个证书)，用于内部 CA 签发的证书"
+    )]
+    pub ca_cert: Option<PathBuf>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_CLIENT_CERT",
+        help = "双向 TLS 的客户端证书 (PEM)，私钥可写在同一文件或通过 --client-key 指定"
+    )]
+    pub client_cert: Option<PathBuf>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_CLIENT_KEY",
+        help = "客户端证书的私钥 (PEM)"
+    )]
+    pub client_key: Option<PathBuf>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_INSECURE",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        help = "不校验 Gewe 服务器的 TLS 证书，仅用于自签名证书的测试环境"
+    )]
+    pub insecure: Option<bool>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_QUIET_HOURS",
+        value_delimiter = ',',
+        help = "免打扰时段，如 22:00-08:00，多个时段以逗号分隔；critical 级别的通知不受影响"
+    )]
+    pub quiet_hours: Option<Vec<String>>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_QUIET_HOURS_TIMEZONE",
+        help = "判断免打扰时段所用的时区，如 Asia/Shanghai [默认: local，即本机时区]"
+    )]
+    pub quiet_hours_timezone: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_QUIET_HOURS_MODE",
+        help = "免打扰时段内的处理方式: digest 暂存并在时段结束后汇总发送，silent 照常发送但不 @ 任何人 [默认: digest]"
+    )]
+    pub quiet_hours_mode: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_EXEC_SUCCESS_TEMPLATE",
+        help = "exec 子命令在命令成功时发送的通知模板，可用占位符 {command} {status} {exit_code} {duration} {hostname} {output}"
+    )]
+    pub exec_success_template: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_EXEC_FAILURE_TEMPLATE",
+        help = "exec 子命令在命令失败时发送的通知模板，占位符同上"
+    )]
+    pub exec_failure_template: Option<String>,
+
+    #[clap(
+        long,
+        global = true,
+        env = "GEWE_NOTICE_EXEC_TAIL_LINES",
+        help = "exec 子命令的通知中附带的最后输出行数 [默认: 20]"
+    )]
+    pub exec_tail_lines: Option<usize>,
+
+    /// 具名接收者，仅可在配置文件的 `[recipients.<别名>]` 中定义。
+    #[clap(skip)]
+    pub recipients: Option<BTreeMap<String, RecipientProfile>>,
+
+    /// 路由规则，仅可在配置文件的 `[[routes]]` 中定义。
+    #[clap(skip)]
+    pub routes: Option<Vec<RouteProfile>>,
+
+    /// 取自配置文件的键，用于在校验失败时指明出错的文件与键名。
+    #[clap(skip)]
+    pub file_keys: Vec<&'static str>,
 }
 
+impl RawConfig {
+    /// 读取 `--config` 指定的配置文件，用其填补命令行与环境变量均未提供的字段。
+    pub fn with_config_file(mut self) -> Result<Self> {
+        if let Some(path) = &self.config {
+            let file = FileConfig::load(path)?;
+            self.merge_file(file);
+        }
+        Ok(self)
+    }
+
+    /// 合并配置文件。已由命令行或环境变量设置的字段保持不变，
+    /// 因此优先级为: 命令行 > 环境变量 > 配置文件。
+    pub fn merge_file(&mut self, file: FileConfig) {
+        fn fill<T>(
+            slot: &mut Option<T>,
+            value: Option<T>,
+            key: &'static str,
+            keys: &mut Vec<&'static str>,
+        ) {
+            if slot.is_none() && value.is_some() {
+                *slot = value;
+                keys.push(key);
+            }
+        }
+
+        let keys = &mut self.file_keys;
+        fill(&mut self.base_url, file.base_url, "base_url", keys);
+        // token / token_file / token_command 视为同一项：命令行或环境变量指定了任意一种时，
+        // 文件中的 Token 来源整体不生效
+        if self.token.is_none() && self.token_file.is_none() && self.token_command.is_none() {
+            fill(&mut self.token, file.token, "token", keys);
+            fill(&mut self.token_file, file.token_file, "token_file", keys);
+            fill(
+                &mut self.token_command,
+                file.token_command,
+                "token_command",
+                keys,
+            );
+        }
+        fill(&mut self.app_id, file.app_id, "app_id", keys);
+        fill(&mut self.wxid, file.wxid, "wxid", keys);
+        fill(&mut self.at_list, file.at_list, "at_list", keys);
+        fill(
+            &mut self.allowed_recipients,
+            file.allowed_recipients,
+            "allowed_recipients",
+            keys,
+        );
+        fill(
+            &mut self.max_message_length,
+            file.max_message_length,
+            "max_message_length",
+            keys,
+        );
+        fill(
+            &mut self.request_timeout,
+            file.request_timeout,
+            "request_timeout",
+            keys,
+        );
+        fill(
+            &mut self.send_timeout,
+            file.send_timeout,
+            "send_timeout",
+            keys,
+        );
+        fill(
+            &mut self.max_attempts,
+            file.max_attempts,
+            "max_attempts",
+            keys,
+        );
+        fill(
+            &mut self.retry_initial_delay,
+            file.retry_initial_delay,
+            "retry_initial_delay",
+            keys,
+        );
+        fill(
+            &mut self.retry_max_delay,
+            file.retry_max_delay,
+            "retry_max_delay",
+            keys,
+        );
+        fill(
+            &mut self.retry_multiplier,
+            file.retry_multiplier,
+            "retry_multiplier",
+            keys,
+        );
+        fill(
+            &mut self.max_concurrency,
+            file.max_concurrency,
+            "max_concurrency",
+            keys,
+        );
+        fill(
+            &mut self.failback_interval,
+            file.failback_interval,
+            "failback_interval",
+            keys,
+        );
+        fill(&mut self.proxy, file.proxy, "proxy", keys);
+        fill(&mut self.ca_cert, file.ca_cert, "ca_cert", keys);
+        fill(&mut self.client_cert, file.client_cert, "client_cert", keys);
+        fill(&mut self.client_key, file.client_key, "client_key", keys);
+        fill(&mut self.insecure, file.insecure, "insecure", keys);
+        fill(&mut self.quiet_hours, file.quiet_hours, "quiet_hours", keys);
+        fill(
+            &mut self.quiet_hours_timezone,
+            file.quiet_hours_timezone,
+            "quiet_hours_timezone",
+            keys,
+        );
+        fill(
+            &mut self.quiet_hours_mode,
+            file.quiet_hours_mode,
+            "quiet_hours_mode",
+            keys,
+        );
+        fill(
+            &mut self.exec_success_template,
+            file.exec_success_template,
+            "exec_success_template",
+            keys,
+        );
+        fill(
+            &mut self.exec_failure_template,
+            file.exec_failure_template,
+            "exec_failure_template",
+            keys,
+        );
+        fill(
+            &mut self.exec_tail_lines,
+            file.exec_tail_lines,
+            "exec_tail_lines",
+            keys,
+        );
+        fill(&mut self.recipients, file.recipients, "recipients", keys);
+        fill(&mut self.routes, file.routes, "routes", keys);
+    }
+
+    /// 若出错的键来自配置文件，则在错误中附上文件路径与键名。
+    fn attribute(&self, key: &'static str, err: GeweNoticeError) -> GeweNoticeError {
+        match (&self.config, err) {
+            (Some(path), GeweNoticeError::Config(reason)) if self.file_keys.contains(&key) => {
+                ConfigValidationError::InFile {
+                    path: path.display().to_string(),
+                    key,
+                    reason: Box::new(reason),
+                }
+                .into()
+            }
+            (_, err) => err,
+        }
+    }
+}
+
+/// Gewe API Token 的来源。来自文件或命令时，API 返回鉴权失败后会重新读取。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TokenSource {
+    /// 直接通过 `--token`、环境变量或配置文件的 `token` 提供。
+    #[default]
+    Inline,
+    File(PathBuf),
+    Command(String),
+}
+
+impl TokenSource {
+    pub fn is_refreshable(&self) -> bool {
+        !matches!(self, Self::Inline)
+    }
+
+    /// 从文件或命令读取并校验 Token；`Inline` 没有可重新读取的来源，返回 `None`。
+    pub fn read(&self) -> Result<Option<ValidatedToken>> {
+        let raw = match self {
+            Self::Inline => return Ok(None),
+            Self::File(path) => std::fs::read_to_string(path).map_err(|e| self.failed(e))?,
+            Self::Command(command) => {
+                run_token_command(command, TOKEN_COMMAND_TIMEOUT).map_err(|e| self.failed(e))?
+            }
+        };
+
+        ValidatedToken::new(raw.trim()).map(Some)
+    }
+
+    fn failed(&self, reason: impl fmt::Display) -> GeweNoticeError {
+        ConfigValidationError::TokenSourceFailed {
+            origin: self.to_string(),
+            reason: reason.to_string(),
+        }
+        .into()
+    }
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inline => write!(f, "配置"),
+            Self::File(path) => write!(f, "文件 {}", path.display()),
+            Self::Command(command) => write!(f, "命令 `{}`", command),
+        }
+    }
+}
+
+/// Token 命令的最长运行时间。密码管理器等待交互解锁时不会让启动、热重载或发送一直挂起。
+pub const TOKEN_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 运行 Token 命令并返回其标准输出；超过 `limit` 仍未结束时终止命令并报错。
+fn run_token_command(command: &str, limit: Duration) -> std::result::Result<String, String> {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let mut child = shell_command(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    // 在后台线程中读取输出，避免输出填满管道后命令阻塞；
+    // 超时后命令的子进程可能仍占用管道，因此不等待这些线程结束。
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = drain(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+
+    let deadline = std::time::Instant::now() + limit;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if std::time::Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("命令在 {:?} 内没有结束，已终止", limit));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(format!("{} {}", status, stderr.trim()));
+    }
+    String::from_utf8(stdout).map_err(|e| e.to_string())
+}
+
+#[cfg(unix)]
+fn shell_command(command: &str) -> std::process::Command {
+    let mut cmd = std::process::Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> std::process::Command {
+    let mut cmd = std::process::Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+fn invalid_value(
+    key: &'static str,
+    value: impl fmt::Display,
+    reason: impl Into<String>,
+) -> GeweNoticeError {
+    ConfigValidationError::InvalidValue {
+        key,
+        value: value.to_string(),
+        reason: reason.into(),
+    }
+    .into()
+}
+
+fn required(value: Option<String>, key: &'static str, env: &'static str) -> Result<String> {
+    value.ok_or_else(|| ConfigValidationError::Missing { key, env }.into())
+}
+
+/// 一次发送的目标：接收者及其 @ 列表。
+#[derive(Debug, Clone)]
+pub struct Recipient {
+    pub wxid: WxId,
+    pub at_list: Option<Vec<WxId>>,
+}
+
+impl Recipient {
+    pub fn is_chatroom(&self) -> bool {
+        self.wxid.is_chatroom()
+    }
+
+    pub fn normalized_at_list(&self) -> Option<Vec<String>> {
+        self.at_list
+            .as_ref()
+            .map(|list| list.iter().map(|wxid| wxid.as_str().to_string()).collect())
+    }
+
+    /// `at_list` 是否只有 `all`，即 @所有人。`all` 与其他 wxid 混用时按普通 wxid 处理。
+    pub fn mentions_all(&self) -> bool {
+        matches!(self.at_list.as_deref(), Some([only]) if only.is_all())
+    }
+}
+
+/// 文件传输助手，只查询不发送时作为默认接收者的占位。
+const LOOKUP_PLACEHOLDER_WXID: &str = "filehelper";
+
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub base_url: String,
+    /// 按优先级排列的 Gewe API 地址，至少一个；第一个为主地址。
+    pub base_urls: Vec<String>,
     pub token: ValidatedToken,
+    /// Token 的来源，用于鉴权失败后重新读取。
+    pub token_source: TokenSource,
     pub app_id: AppId,
     pub wxid: WxId,
     pub at_list: Option<Vec<WxId>>,
+    pub allowed_recipients: Vec<WxId>,
+    pub max_message_length: usize,
+    pub http: HttpSettings,
+    pub transport: TransportSettings,
+    /// 免打扰设置，未配置时段时为 `None`。
+    pub quiet_hours: Option<QuietHours>,
+    /// `exec` 子命令的通知模板与输出行数。
+    pub exec: ExecSettings,
+    /// 按别名选择的具名接收者，按别名排序。
+    pub recipients: BTreeMap<String, Recipient>,
+    /// 按顺序评估的路由规则，见 [`crate::routing::route`]。
+    pub routes: Vec<RouteRule>,
+    /// 加载时使用的配置文件（若有）。
+    pub config_file: Option<PathBuf>,
 }
 
 impl Config {
-    pub fn parse() -> Result<Self> {
-        let raw = RawConfig::parse();
+    /// 合并 `--config` 配置文件后校验。启动与重新加载共用，
+    /// 因此重新加载时命令行与环境变量仍然优先于文件。
+    pub fn load(raw: RawConfig) -> Result<Self> {
+        Self::from_raw(raw.with_config_file()?)
+    }
+
+    /// 供只查询、不发送的子命令使用 (`contacts`、`members`)。此时用户往往还不知道接收者的 wxid，
+    /// 因此未配置 `wxid` 时以文件传输助手占位，其余配置照常校验。
+    pub fn load_for_lookup(raw: RawConfig) -> Result<Self> {
+        let mut raw = raw.with_config_file()?;
+        raw.wxid
+            .get_or_insert_with(|| LOOKUP_PLACEHOLDER_WXID.to_string());
         Self::from_raw(raw)
     }
 
     pub fn from_raw(raw: RawConfig) -> Result<Self> {
-        let token = ValidatedToken::new(&raw.token)?;
-        let app_id = AppId::new(raw.app_id)?;
-        let wxid = WxId::new(raw.wxid)?;
+        let (token_source, token_key) = match (&raw.token, &raw.token_file, &raw.token_command) {
+            (_, None, None) => (TokenSource::Inline, "token"),
+            (None, Some(path), None) => (TokenSource::File(path.clone()), "token_file"),
+            (None, None, Some(command)) => (TokenSource::Command(command.clone()), "token_command"),
+            _ => return Err(ConfigValidationError::ConflictingTokenSources.into()),
+        };
+        let token = match token_source.read() {
+            Ok(Some(token)) => Ok(token),
+            Ok(None) => required(raw.token.clone(), "token", "GEWE_NOTICE_TOKEN")
+                .and_then(|token| ValidatedToken::new(&token)),
+            Err(e) => Err(e),
+        }
+        .map_err(|e| raw.attribute(token_key, e))?;
+        let app_id = required(raw.app_id.clone(), "app_id", "GEWE_NOTICE_APP_ID")
+            .and_then(AppId::new)
+            .map_err(|e| raw.attribute("app_id", e))?;
+        let wxid = required(raw.wxid.clone(), "wxid", "GEWE_NOTICE_WXID")
+            .and_then(WxId::new)
+            .map_err(|e| raw.attribute("wxid", e))?;
 
-        let at_list = match raw.at_list {
+        let at_list = match raw.at_list.clone() {
             Some(list) => {
-                let mut validated = Vec::new();
-                for id in list {
-                    let trimmed = id.trim();
-                    if !trimmed.is_empty() {
-                        validated.push(WxId::new(trimmed.to_string())?);
-                    }
-                }
+                let validated =
+                    Self::parse_wxid_list(list).map_err(|e| raw.attribute("at_list", e))?;
                 if validated.is_empty() {
                     None
                 } else {
@@ -165,20 +850,474 @@ impl Config {
             }
             None => None,
         };
+        let allowed_recipients =
+            Self::parse_wxid_list(raw.allowed_recipients.clone().unwrap_or_default())
+                .map_err(|e| raw.attribute("allowed_recipients", e))?;
+
+        let max_message_length = raw.max_message_length.unwrap_or(DEFAULT_MAX_MESSAGE_LENGTH);
+        if max_message_length < MIN_MAX_MESSAGE_LENGTH {
+            let err = ConfigValidationError::InvalidMaxLength {
+                value: max_message_length,
+                min: MIN_MAX_MESSAGE_LENGTH,
+            };
+            return Err(raw.attribute("max_message_length", err.into()));
+        }
+
+        let base_urls = Self::parse_base_urls(raw.base_url.clone())
+            .map_err(|e| raw.attribute("base_url", e))?;
+        let http = Self::parse_http_settings(&raw)?;
+        let transport = Self::parse_transport_settings(&raw)?;
+        let quiet_hours = Self::parse_quiet_hours(&raw)?;
+        let exec = Self::parse_exec_settings(&raw)?;
+
+        let recipients = Self::parse_recipients(raw.recipients.clone().unwrap_or_default())
+            .map_err(|e| raw.attribute("recipients", e))?;
+        let routes = Self::parse_routes(raw.routes.clone().unwrap_or_default(), &recipients)
+            .map_err(|e| raw.attribute("routes", e))?;
 
         Ok(Self {
-            base_url: raw.base_url,
+            base_urls,
             token,
+            token_source,
             app_id,
             wxid,
             at_list,
+            allowed_recipients,
+            max_message_length,
+            http,
+            transport,
+            quiet_hours,
+            exec,
+            recipients,
+            routes,
+            config_file: raw.config,
+        })
+    }
+
+    pub(crate) fn parse_wxid_list(list: Vec<String>) -> Result<Vec<WxId>> {
+        let mut validated = Vec::new();
+        for id in list {
+            let trimmed = id.trim();
+            if !trimmed.is_empty() {
+                validated.push(WxId::new(trimmed.to_string())?);
+            }
+        }
+        Ok(validated)
+    }
+
+    fn parse_base_urls(urls: Option<Vec<String>>) -> Result<Vec<String>> {
+        let mut validated = Vec::new();
+        for url in urls.unwrap_or_default() {
+            let url = url.trim().trim_end_matches('/');
+            if url.is_empty() {
+                continue;
+            }
+            match reqwest::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+                Ok(_) => return Err(invalid_value("base_url", url, "只支持 http 或 https")),
+                Err(e) => return Err(invalid_value("base_url", url, e.to_string())),
+            }
+            if !validated.iter().any(|existing| existing == url) {
+                validated.push(url.to_string());
+            }
+        }
+        if validated.is_empty() {
+            validated.push(DEFAULT_BASE_URL.to_string());
+        }
+        Ok(validated)
+    }
+
+    fn parse_http_settings(raw: &RawConfig) -> Result<HttpSettings> {
+        let defaults = HttpSettings::default();
+        let duration = |key: &'static str, value: &Option<String>, default: Duration| {
+            let Some(value) = value else {
+                return Ok(default);
+            };
+            match parse_duration(value) {
+                Ok(duration) if duration.is_zero() => {
+                    Err(raw.attribute(key, invalid_value(key, value, "必须大于 0")))
+                }
+                Ok(duration) => Ok(duration),
+                Err(reason) => Err(raw.attribute(key, invalid_value(key, value, reason))),
+            }
+        };
+
+        let request_timeout = duration(
+            "request_timeout",
+            &raw.request_timeout,
+            defaults.request_timeout,
+        )?;
+        let send_timeout = duration("send_timeout", &raw.send_timeout, defaults.send_timeout)?;
+        if send_timeout < request_timeout {
+            return Err(raw.attribute(
+                "send_timeout",
+                invalid_value(
+                    "send_timeout",
+                    format!("{:?}", send_timeout),
+                    format!("不能小于 request_timeout ({:?})", request_timeout),
+                ),
+            ));
+        }
+
+        let initial_delay = duration(
+            "retry_initial_delay",
+            &raw.retry_initial_delay,
+            defaults.retry.initial_delay,
+        )?;
+        let max_delay = duration(
+            "retry_max_delay",
+            &raw.retry_max_delay,
+            defaults.retry.max_delay,
+        )?;
+        if max_delay < initial_delay {
+            return Err(raw.attribute(
+                "retry_max_delay",
+                invalid_value(
+                    "retry_max_delay",
+                    format!("{:?}", max_delay),
+                    format!("不能小于 retry_initial_delay ({:?})", initial_delay),
+                ),
+            ));
+        }
+
+        let max_attempts = raw.max_attempts.unwrap_or(defaults.retry.max_attempts);
+        if !(1..=MAX_ATTEMPTS_LIMIT).contains(&max_attempts) {
+            return Err(raw.attribute(
+                "max_attempts",
+                invalid_value(
+                    "max_attempts",
+                    max_attempts,
+                    format!("应在 1 到 {} 之间", MAX_ATTEMPTS_LIMIT),
+                ),
+            ));
+        }
+
+        let multiplier = raw.retry_multiplier.unwrap_or(defaults.retry.multiplier);
+        if !(multiplier >= 1.0 && multiplier.is_finite()) {
+            return Err(raw.attribute(
+                "retry_multiplier",
+                invalid_value("retry_multiplier", multiplier, "不能小于 1"),
+            ));
+        }
+
+        let failback_interval = duration(
+            "failback_interval",
+            &raw.failback_interval,
+            defaults.failback_interval,
+        )?;
+
+        let max_concurrency = raw.max_concurrency.unwrap_or(defaults.max_concurrency);
+        if max_concurrency == 0 {
+            return Err(raw.attribute(
+                "max_concurrency",
+                invalid_value("max_concurrency", max_concurrency, "至少为 1"),
+            ));
+        }
+
+        Ok(HttpSettings {
+            request_timeout,
+            send_timeout,
+            retry: RetryPolicy {
+                max_attempts,
+                initial_delay,
+                max_delay,
+                multiplier,
+            },
+            max_concurrency,
+            failback_interval,
         })
     }
 
+    fn parse_transport_settings(raw: &RawConfig) -> Result<TransportSettings> {
+        let read = |key: &'static str, path: &PathBuf| {
+            std::fs::read(path).map_err(|e| {
+                raw.attribute(
+                    key,
+                    invalid_value(key, path.display(), format!("无法读取: {}", e)),
+                )
+            })
+        };
+
+        if let Some(proxy) = &raw.proxy {
+            reqwest::Proxy::all(proxy.as_str()).map_err(|e| {
+                raw.attribute("proxy", invalid_value("proxy", proxy, error_chain(&e)))
+            })?;
+        }
+
+        let ca_certs = match &raw.ca_cert {
+            Some(path) => {
+                let certs = reqwest::Certificate::from_pem_bundle(&read("ca_cert", path)?)
+                    .map_err(|e| {
+                        raw.attribute(
+                            "ca_cert",
+                            invalid_value("ca_cert", path.display(), error_chain(&e)),
+                        )
+                    })?;
+                if certs.is_empty() {
+                    return Err(raw.attribute(
+                        "ca_cert",
+                        invalid_value("ca_cert", path.display(), "文件中没有 PEM 格式的证书"),
+                    ));
+                }
+                certs
+            }
+            None => Vec::new(),
+        };
+
+        let client_identity = match (&raw.client_cert, &raw.client_key) {
+            (Some(cert), key) => {
+                let mut pem = read("client_cert", cert)?;
+                if let Some(key) = key {
+                    pem.push(b'\n');
+                    pem.extend(read("client_key", key)?);
+                }
+                let identity = reqwest::Identity::from_pem(&pem).map_err(|e| {
+                    raw.attribute(
+                        "client_cert",
+                        invalid_value("client_cert", cert.display(), error_chain(&e)),
+                    )
+                })?;
+                Some(identity)
+            }
+            (None, Some(key)) => {
+                return Err(raw.attribute(
+                    "client_key",
+                    invalid_value("client_key", key.display(), "需要同时指定 client_cert"),
+                ));
+            }
+            (None, None) => None,
+        };
+
+        Ok(TransportSettings {
+            proxy: raw.proxy.clone(),
+            ca_certs,
+            client_identity,
+            insecure: raw.insecure.unwrap_or(false),
+        })
+    }
+
+    fn parse_quiet_hours(raw: &RawConfig) -> Result<Option<QuietHours>> {
+        let mut windows = Vec::new();
+        for window in raw.quiet_hours.iter().flatten() {
+            if window.trim().is_empty() {
+                continue;
+            }
+            let parsed = window.parse::<QuietWindow>().map_err(|reason| {
+                raw.attribute("quiet_hours", invalid_value("quiet_hours", window, reason))
+            })?;
+            windows.push(parsed);
+        }
+
+        let timezone = match &raw.quiet_hours_timezone {
+            Some(value) => value.parse::<QuietTimezone>().map_err(|reason| {
+                raw.attribute(
+                    "quiet_hours_timezone",
+                    invalid_value("quiet_hours_timezone", value, reason),
+                )
+            })?,
+            None => QuietTimezone::default(),
+        };
+        let mode = match &raw.quiet_hours_mode {
+            Some(value) => value.parse::<QuietMode>().map_err(|reason| {
+                raw.attribute(
+                    "quiet_hours_mode",
+                    invalid_value("quiet_hours_mode", value, reason),
+                )
+            })?,
+            None => QuietMode::default(),
+        };
+
+        if windows.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(QuietHours {
+            windows,
+            timezone,
+            mode,
+        }))
+    }
+
+    fn parse_exec_settings(raw: &RawConfig) -> Result<ExecSettings> {
+        let defaults = ExecSettings::default();
+        let template = |key: &'static str, value: &Option<String>, default: Template| match value {
+            Some(value) => value
+                .parse::<Template>()
+                .map_err(|reason| raw.attribute(key, invalid_value(key, value, reason))),
+            None => Ok(default),
+        };
+
+        Ok(ExecSettings {
+            success_template: template(
+                "exec_success_template",
+                &raw.exec_success_template,
+                defaults.success_template,
+            )?,
+            failure_template: template(
+                "exec_failure_template",
+                &raw.exec_failure_template,
+                defaults.failure_template,
+            )?,
+            tail_lines: raw.exec_tail_lines.unwrap_or(defaults.tail_lines),
+        })
+    }
+
+    fn parse_recipients(
+        profiles: BTreeMap<String, RecipientProfile>,
+    ) -> Result<BTreeMap<String, Recipient>> {
+        let mut recipients = BTreeMap::new();
+        for (alias, profile) in profiles {
+            let valid_alias = !alias.is_empty()
+                && alias
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_alias {
+                return Err(ConfigValidationError::InvalidAlias { alias }.into());
+            }
+
+            let recipient = WxId::new(profile.wxid.trim().to_string())
+                .and_then(|wxid| {
+                    let at_list = Self::parse_wxid_list(profile.at_list.unwrap_or_default())?;
+                    Ok(Recipient {
+                        wxid,
+                        at_list: (!at_list.is_empty()).then_some(at_list),
+                    })
+                })
+                .map_err(|e| match e {
+                    GeweNoticeError::Config(reason) => ConfigValidationError::InvalidRecipient {
+                        alias: alias.clone(),
+                        reason: Box::new(reason),
+                    }
+                    .into(),
+                    other => other,
+                })?;
+            recipients.insert(alias, recipient);
+        }
+        Ok(recipients)
+    }
+
+    fn parse_routes(
+        profiles: Vec<RouteProfile>,
+        recipients: &BTreeMap<String, Recipient>,
+    ) -> Result<Vec<RouteRule>> {
+        profiles
+            .into_iter()
+            .enumerate()
+            .map(|(index, profile)| {
+                let name = profile.name.unwrap_or_else(|| format!("#{}", index + 1));
+                let invalid = |reason: String| -> GeweNoticeError {
+                    ConfigValidationError::InvalidRoute {
+                        rule: name.clone(),
+                        reason,
+                    }
+                    .into()
+                };
+
+                let severity = profile
+                    .severity
+                    .iter()
+                    .map(|value| value.parse::<Severity>())
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(invalid)?;
+
+                if profile.recipients.is_empty() {
+                    return Err(invalid("recipients 不能为空".to_string()));
+                }
+                if let Some(unknown) = profile.recipients.iter().find(|alias| {
+                    alias.as_str() != DEFAULT_ROUTE_TARGET && !recipients.contains_key(*alias)
+                }) {
+                    return Err(invalid(format!(
+                        "未定义的接收者 '{}' (可用: {})",
+                        unknown,
+                        std::iter::once(DEFAULT_ROUTE_TARGET)
+                            .chain(recipients.keys().map(String::as_str))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )));
+                }
+
+                let at_list = profile
+                    .at_list
+                    .map(Self::parse_wxid_list)
+                    .transpose()
+                    .map_err(|e| invalid(e.to_string()))?;
+
+                Ok(RouteRule {
+                    name: name.clone(),
+                    severity,
+                    tags: profile.tags,
+                    source: profile.source,
+                    recipients: profile.recipients,
+                    at_list,
+                })
+            })
+            .collect()
+    }
+
     pub fn is_chatroom(&self) -> bool {
         self.wxid.is_chatroom()
     }
 
+    /// 主地址，即 `base_urls` 中的第一个；`base_urls` 为空时为空字符串。
+    pub fn primary_base_url(&self) -> &str {
+        self.base_urls.first().map_or("", String::as_str)
+    }
+
+    /// 确认至少有一个 Gewe 地址。[`Self::from_raw`] 总会补上默认地址，
+    /// 直接构造 `Config` 时由 [`crate::gewe_api::GeweApiClient`] 检查。
+    pub fn ensure_base_url(&self) -> Result<()> {
+        if self.base_urls.is_empty() {
+            return Err(ConfigValidationError::Missing {
+                key: "base_url",
+                env: "GEWE_NOTICE_BASE_URL",
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// 按别名查找具名接收者。
+    pub fn recipient_by_alias(&self, alias: &str) -> Option<Recipient> {
+        self.recipients.get(alias).cloned()
+    }
+
+    /// 所有具名接收者的别名，按字母序排列。
+    pub fn recipient_aliases(&self) -> Vec<String> {
+        self.recipients.keys().cloned().collect()
+    }
+
+    /// 默认接收者，即 `GEWE_NOTICE_WXID` 与 `GEWE_NOTICE_AT_LIST`。
+    pub fn default_recipient(&self) -> Recipient {
+        Recipient {
+            wxid: self.wxid.clone(),
+            at_list: self.at_list.clone(),
+        }
+    }
+
+    /// 按次覆盖的接收者。默认接收者以外的目标需要出现在允许列表中，
+    /// 且不沿用默认的 @ 列表（这些成员通常不在另一个群里）。
+    /// 具名接收者同样允许，并使用其自身的 @ 列表。
+    pub fn recipient_override(&self, wxid: &WxId) -> Option<Recipient> {
+        if wxid.as_str() == self.wxid_str() {
+            return Some(self.default_recipient());
+        }
+
+        if let Some(recipient) = self
+            .recipients
+            .values()
+            .find(|recipient| recipient.wxid.as_str() == wxid.as_str())
+        {
+            return Some(recipient.clone());
+        }
+
+        self.allowed_recipients
+            .iter()
+            .any(|allowed| allowed.as_str() == wxid.as_str())
+            .then(|| Recipient {
+                wxid: wxid.clone(),
+                at_list: None,
+            })
+    }
+
     pub fn redact(&self, value: &str, show_first: usize, show_last: usize) -> String {
         if value.len() <= show_first + show_last {
             "****".to_string()
@@ -216,14 +1355,98 @@ mod tests {
 
     fn base_raw_config() -> RawConfig {
         RawConfig {
-            base_url: "https://example.com".into(),
-            token: "00000000-0000-0000-0000-000000000000".into(),
-            app_id: "wx_app".into(),
-            wxid: "wxid_sample".into(),
+            config: None,
+            base_url: Some(vec!["https://example.com".into()]),
+            token: Some("00000000-0000-0000-0000-000000000000".into()),
+            token_file: None,
+            token_command: None,
+            app_id: Some("wx_app".into()),
+            wxid: Some("wxid_sample".into()),
             at_list: None,
+            allowed_recipients: None,
+            max_message_length: None,
+            request_timeout: None,
+            send_timeout: None,
+            max_attempts: None,
+            retry_initial_delay: None,
+            retry_max_delay: None,
+            retry_multiplier: None,
+            max_concurrency: None,
+            failback_interval: None,
+            proxy: None,
+            ca_cert: None,
+            client_cert: None,
+            client_key: None,
+            insecure: None,
+            quiet_hours: None,
+            quiet_hours_timezone: None,
+            quiet_hours_mode: None,
+            exec_success_template: None,
+            exec_failure_template: None,
+            exec_tail_lines: None,
+            recipients: None,
+            routes: None,
+            file_keys: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn quiet_hours_are_parsed_and_validated() {
+        let config = Config::from_raw(base_raw_config()).expect("valid config");
+        assert!(config.quiet_hours.is_none());
+
+        let mut raw = base_raw_config();
+        raw.quiet_hours = Some(vec!["22:00-08:00".into(), " 12:00-13:30 ".into()]);
+        raw.quiet_hours_timezone = Some("Asia/Shanghai".into());
+        raw.quiet_hours_mode = Some("silent".into());
+        let quiet = Config::from_raw(raw)
+            .expect("valid config")
+            .quiet_hours
+            .expect("quiet hours");
+        assert_eq!(quiet.windows.len(), 2);
+        assert_eq!(quiet.windows[1].to_string(), "12:00-13:30");
+        assert_eq!(quiet.timezone.to_string(), "Asia/Shanghai");
+        assert_eq!(quiet.mode, QuietMode::Silent);
+
+        for (field, value) in [
+            ("quiet_hours", "22:00"),
+            ("quiet_hours_timezone", "Moon/Base"),
+            ("quiet_hours_mode", "loud"),
+        ] {
+            let mut raw = base_raw_config();
+            raw.quiet_hours = Some(vec!["22:00-08:00".into()]);
+            match field {
+                "quiet_hours" => raw.quiet_hours = Some(vec![value.into()]),
+                "quiet_hours_timezone" => raw.quiet_hours_timezone = Some(value.into()),
+                _ => raw.quiet_hours_mode = Some(value.into()),
+            }
+            let err = Config::from_raw(raw).expect_err("invalid quiet hours");
+            assert!(err.to_string().contains(field), "{err}");
         }
     }
 
+    #[test]
+    fn exec_templates_are_validated() {
+        let mut raw = base_raw_config();
+        raw.exec_failure_template = Some("{command} 失败: {status}".into());
+        raw.exec_tail_lines = Some(5);
+        let config = Config::from_raw(raw).expect("valid config");
+        assert_eq!(
+            config.exec.failure_template.to_string(),
+            "{command} 失败: {status}"
+        );
+        assert_eq!(
+            config.exec.success_template,
+            ExecSettings::default().success_template
+        );
+        assert_eq!(config.exec.tail_lines, 5);
+
+        let mut raw = base_raw_config();
+        raw.exec_success_template = Some("{cmd} 完成".into());
+        let err = Config::from_raw(raw).expect_err("unknown placeholder");
+        assert!(err.to_string().contains("exec_success_template"), "{err}");
+    }
+
     #[test]
     fn normalized_handles_none() {
         let raw = base_raw_config();
@@ -251,10 +1474,39 @@ mod tests {
         assert_eq!(result, vec!["all".to_string()]);
     }
 
+    #[test]
+    fn recipient_override_requires_allowlist() {
+        let mut raw = base_raw_config();
+        raw.allowed_recipients = Some(vec![" ops@chatroom ".into(), "".into()]);
+
+        let config = Config::from_raw(raw).expect("valid config");
+        assert_eq!(config.allowed_recipients.len(), 1);
+
+        let ops = WxId::new("ops@chatroom".into()).unwrap();
+        let recipient = config.recipient_override(&ops).expect("allowlisted");
+        assert_eq!(recipient.wxid.as_str(), "ops@chatroom");
+        assert!(recipient.at_list.is_none());
+
+        let default = WxId::new("wxid_sample".into()).unwrap();
+        assert!(config.recipient_override(&default).is_some());
+
+        let stranger = WxId::new("wxid_stranger".into()).unwrap();
+        assert!(config.recipient_override(&stranger).is_none());
+    }
+
+    #[test]
+    fn test_max_message_length_too_small() {
+        let mut raw = base_raw_config();
+        raw.max_message_length = Some(MIN_MAX_MESSAGE_LENGTH - 1);
+
+        let result = Config::from_raw(raw);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_invalid_token() {
         let mut raw = base_raw_config();
-        raw.token = "not-a-uuid".into();
+        raw.token = Some("not-a-uuid".into());
 
         let result = Config::from_raw(raw);
         assert!(result.is_err());
@@ -263,7 +1515,7 @@ mod tests {
     #[test]
     fn test_invalid_app_id() {
         let mut raw = base_raw_config();
-        raw.app_id = "invalid_app_id".into();
+        raw.app_id = Some("invalid_app_id".into());
 
         let result = Config::from_raw(raw);
         assert!(result.is_err());
@@ -272,7 +1524,7 @@ mod tests {
     #[test]
     fn test_empty_wxid() {
         let mut raw = base_raw_config();
-        raw.wxid = "".into();
+        raw.wxid = Some("".into());
 
         let result = Config::from_raw(raw);
         assert!(result.is_err());
@@ -281,9 +1533,354 @@ mod tests {
     #[test]
     fn test_invalid_chatroom_format() {
         let mut raw = base_raw_config();
-        raw.wxid = "something@chatroommore".into();
+        raw.wxid = Some("something@chatroommore".into());
 
         let result = Config::from_raw(raw);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn file_fills_only_missing_fields() {
+        let mut raw = base_raw_config();
+        raw.wxid = None;
+        raw.merge_file(FileConfig {
+            base_url: Some(vec!["https://file.example.com".into()]),
+            wxid: Some("ops@chatroom".into()),
+            max_message_length: Some(500),
+            ..FileConfig::default()
+        });
+
+        let config = Config::from_raw(raw).expect("valid config");
+        assert_eq!(config.base_urls, vec!["https://example.com"]);
+        assert_eq!(config.wxid_str(), "ops@chatroom");
+        assert_eq!(config.max_message_length, 500);
+    }
+
+    #[test]
+    fn missing_required_field_names_env_var() {
+        let mut raw = base_raw_config();
+        raw.token = None;
+
+        let err = Config::from_raw(raw).expect_err("token required");
+        assert!(err.to_string().contains("GEWE_NOTICE_TOKEN"));
+    }
+
+    #[test]
+    fn invalid_file_value_names_file_and_key() {
+        let mut raw = base_raw_config();
+        raw.config = Some(PathBuf::from("/etc/gewe.toml"));
+        raw.app_id = None;
+        raw.merge_file(FileConfig {
+            app_id: Some("bad".into()),
+            ..FileConfig::default()
+        });
+
+        let err = Config::from_raw(raw).expect_err("invalid app_id");
+        let message = err.to_string();
+        assert!(message.contains("/etc/gewe.toml"), "{message}");
+        assert!(message.contains("`app_id`"), "{message}");
+    }
+
+    #[test]
+    fn recipient_profiles_resolve_by_alias_and_wxid() {
+        let mut raw = base_raw_config();
+        raw.merge_file(FileConfig {
+            recipients: Some(BTreeMap::from([
+                (
+                    "ops".to_string(),
+                    RecipientProfile {
+                        wxid: "ops@chatroom".into(),
+                        at_list: Some(vec!["wxid_oncall".into()]),
+                    },
+                ),
+                (
+                    "me".to_string(),
+                    RecipientProfile {
+                        wxid: "wxid_me".into(),
+                        at_list: None,
+                    },
+                ),
+            ])),
+            ..FileConfig::default()
+        });
+
+        let config = Config::from_raw(raw).expect("valid config");
+        assert_eq!(config.recipient_aliases(), vec!["me", "ops"]);
+
+        let ops = config.recipient_by_alias("ops").expect("ops profile");
+        assert_eq!(ops.wxid.as_str(), "ops@chatroom");
+        assert_eq!(
+            ops.normalized_at_list(),
+            Some(vec!["wxid_oncall".to_string()])
+        );
+        assert!(config.recipient_by_alias("release").is_none());
+
+        let by_wxid = config
+            .recipient_override(&WxId::new("ops@chatroom".into()).unwrap())
+            .expect("profiles are allowed targets");
+        assert!(by_wxid.at_list.is_some());
+    }
+
+    #[test]
+    fn invalid_recipient_profile_names_alias() {
+        let mut raw = base_raw_config();
+        raw.config = Some(PathBuf::from("gewe.toml"));
+        raw.merge_file(FileConfig {
+            recipients: Some(BTreeMap::from([(
+                "release-group".to_string(),
+                RecipientProfile {
+                    wxid: "oops@chatroomx".into(),
+                    at_list: None,
+                },
+            )])),
+            ..FileConfig::default()
+        });
+
+        let message = Config::from_raw(raw).expect_err("invalid wxid").to_string();
+        assert!(message.contains("`recipients`"), "{message}");
+        assert!(message.contains("release-group"), "{message}");
+    }
+
+    #[test]
+    fn recipient_alias_rejects_spaces() {
+        let mut raw = base_raw_config();
+        raw.recipients = Some(BTreeMap::from([(
+            "on call".to_string(),
+            RecipientProfile {
+                wxid: "wxid_me".into(),
+                at_list: None,
+            },
+        )]));
+
+        assert!(Config::from_raw(raw).is_err());
+    }
+
+    #[test]
+    fn routes_validate_severity_and_recipients() {
+        let profile = RecipientProfile {
+            wxid: "ops@chatroom".into(),
+            at_list: None,
+        };
+        let route = RouteProfile {
+            name: Some("failures".into()),
+            severity: vec!["Failure".into()],
+            recipients: vec!["ops".into(), "default".into()],
+            ..RouteProfile::default()
+        };
+
+        let mut raw = base_raw_config();
+        raw.recipients = Some(BTreeMap::from([("ops".to_string(), profile)]));
+        raw.routes = Some(vec![route.clone()]);
+        let config = Config::from_raw(raw.clone()).expect("valid routes");
+        assert_eq!(config.routes[0].severity, vec![Severity::Failure]);
+
+        let mut unknown = route.clone();
+        unknown.recipients = vec!["release".into()];
+        raw.routes = Some(vec![unknown]);
+        let message = Config::from_raw(raw.clone())
+            .expect_err("unknown alias")
+            .to_string();
+        assert!(message.contains("release"), "{message}");
+
+        let mut bad_severity = route;
+        bad_severity.name = None;
+        bad_severity.severity = vec!["fatal".into()];
+        raw.routes = Some(vec![bad_severity]);
+        let message = Config::from_raw(raw).expect_err("bad severity").to_string();
+        assert!(message.contains("#1"), "{message}");
+    }
+
+    #[test]
+    fn token_can_come_from_file() {
+        let path = std::env::temp_dir().join(format!("gewe-token-{}", Uuid::new_v4()));
+        std::fs::write(&path, "11111111-1111-1111-1111-111111111111\n").unwrap();
+
+        let mut raw = base_raw_config();
+        raw.token = None;
+        raw.token_file = Some(path.clone());
+        let config = Config::from_raw(raw).expect("token from file");
+        assert_eq!(config.token_str(), "11111111-1111-1111-1111-111111111111");
+        assert_eq!(config.token_source, TokenSource::File(path.clone()));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn config_file_token_path_is_relative_to_file() {
+        let dir = std::env::temp_dir().join(format!("gewe-config-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("token"), "33333333-3333-3333-3333-333333333333\n").unwrap();
+        std::fs::write(dir.join("config.toml"), "token_file = \"token\"\n").unwrap();
+
+        let mut raw = base_raw_config();
+        raw.token = None;
+        raw.config = Some(dir.join("config.toml"));
+        let config = Config::load(raw).expect("token file next to config");
+        assert_eq!(config.token_str(), "33333333-3333-3333-3333-333333333333");
+        assert_eq!(config.token_source, TokenSource::File(dir.join("token")));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn token_can_come_from_command() {
+        let mut raw = base_raw_config();
+        raw.token = None;
+        raw.token_command = Some("echo 22222222-2222-2222-2222-222222222222".into());
+        let config = Config::from_raw(raw.clone()).expect("token from command");
+        assert_eq!(config.token_str(), "22222222-2222-2222-2222-222222222222");
+
+        raw.token_command = Some("exit 3".into());
+        let message = Config::from_raw(raw)
+            .expect_err("failing command")
+            .to_string();
+        assert!(message.contains("exit 3"), "{message}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn token_command_is_killed_after_timeout() {
+        let started = std::time::Instant::now();
+        let err = run_token_command("sleep 5", Duration::from_millis(200))
+            .expect_err("command times out");
+        assert!(err.contains("已终止"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn token_sources_are_exclusive() {
+        let mut raw = base_raw_config();
+        raw.token_command = Some("pass show gewe".into());
+        assert!(Config::from_raw(raw).is_err());
+    }
+
+    #[test]
+    fn cli_token_overrides_file_token_source() {
+        let mut raw = base_raw_config();
+        raw.merge_file(FileConfig {
+            token_command: Some("pass show gewe".into()),
+            ..FileConfig::default()
+        });
+
+        let config = Config::from_raw(raw).expect("cli token wins");
+        assert_eq!(config.token_source, TokenSource::Inline);
+    }
+
+    #[test]
+    fn parse_duration_accepts_units() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration(" 10s "), Ok(Duration::from_secs(10)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("15"), Ok(Duration::from_secs(15)));
+        assert!(parse_duration("1h").is_err());
+        assert!(parse_duration("fast").is_err());
+        assert!(parse_duration(&format!("{}m", u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn http_settings_default_and_override() {
+        let config = Config::from_raw(base_raw_config()).expect("valid config");
+        assert_eq!(config.http, HttpSettings::default());
+
+        let mut raw = base_raw_config();
+        raw.request_timeout = Some("45s".into());
+        raw.send_timeout = Some("2m".into());
+        raw.max_attempts = Some(6);
+        raw.retry_initial_delay = Some("1s".into());
+        raw.max_concurrency = Some(2);
+        let config = Config::from_raw(raw).expect("valid config");
+        assert_eq!(config.http.request_timeout, Duration::from_secs(45));
+        assert_eq!(config.http.send_timeout, Duration::from_secs(120));
+        assert_eq!(config.http.retry.max_attempts, 6);
+        assert_eq!(config.http.retry.initial_delay, Duration::from_secs(1));
+        assert_eq!(config.http.max_concurrency, 2);
+    }
+
+    #[test]
+    fn http_settings_are_validated() {
+        let mut raw = base_raw_config();
+        raw.send_timeout = Some(format!("{}m", u64::MAX));
+        assert!(Config::from_raw(raw).is_err());
+
+        let mut raw = base_raw_config();
+        raw.request_timeout = Some("1m".into());
+        raw.send_timeout = Some("30s".into());
+        assert!(Config::from_raw(raw).is_err());
+
+        let mut raw = base_raw_config();
+        raw.max_attempts = Some(0);
+        assert!(Config::from_raw(raw).is_err());
+
+        let mut raw = base_raw_config();
+        raw.retry_multiplier = Some(0.5);
+        assert!(Config::from_raw(raw).is_err());
+
+        let mut raw = base_raw_config();
+        raw.config = Some(PathBuf::from("gewe.toml"));
+        raw.merge_file(FileConfig {
+            request_timeout: Some("0s".into()),
+            ..FileConfig::default()
+        });
+        let message = Config::from_raw(raw).expect_err("zero timeout").to_string();
+        assert!(message.contains("gewe.toml"), "{message}");
+        assert!(message.contains("request_timeout"), "{message}");
+    }
+
+    #[test]
+    fn transport_settings_are_validated() {
+        let mut raw = base_raw_config();
+        raw.proxy = Some("http://proxy.corp:3128".into());
+        raw.insecure = Some(true);
+        let config = Config::from_raw(raw).expect("valid proxy");
+        assert_eq!(
+            config.transport.proxy.as_deref(),
+            Some("http://proxy.corp:3128")
+        );
+        assert!(config.transport.insecure);
+
+        let mut raw = base_raw_config();
+        raw.proxy = Some("not a proxy".into());
+        let message = Config::from_raw(raw).expect_err("bad proxy").to_string();
+        assert!(message.contains("proxy"), "{message}");
+
+        let path = std::env::temp_dir().join(format!("gewe-ca-{}.pem", Uuid::new_v4()));
+        std::fs::write(&path, "not a certificate").unwrap();
+        let mut raw = base_raw_config();
+        raw.ca_cert = Some(path.clone());
+        let message = Config::from_raw(raw).expect_err("bad ca").to_string();
+        std::fs::remove_file(&path).ok();
+        assert!(message.contains("ca_cert"), "{message}");
+
+        let mut raw = base_raw_config();
+        raw.client_key = Some(PathBuf::from("client.key"));
+        let message = Config::from_raw(raw).expect_err("key only").to_string();
+        assert!(message.contains("client_cert"), "{message}");
+    }
+
+    #[test]
+    fn base_urls_are_ordered_and_validated() {
+        let mut raw = base_raw_config();
+        raw.base_url = Some(vec![
+            "https://primary.example.com/".into(),
+            " https://standby.example.com".into(),
+            "https://primary.example.com".into(),
+        ]);
+        let config = Config::from_raw(raw).expect("valid urls");
+        assert_eq!(
+            config.base_urls,
+            vec!["https://primary.example.com", "https://standby.example.com"]
+        );
+        assert_eq!(config.primary_base_url(), "https://primary.example.com");
+
+        let mut raw = base_raw_config();
+        raw.base_url = None;
+        let config = Config::from_raw(raw).expect("default url");
+        assert_eq!(config.base_urls, vec![DEFAULT_BASE_URL]);
+
+        let mut raw = base_raw_config();
+        raw.base_url = Some(vec!["ftp://gewe.example.com".into()]);
+        let message = Config::from_raw(raw).expect_err("bad scheme").to_string();
+        assert!(message.contains("base_url"), "{message}");
+    }
 }
@@ -0,0 +1,218 @@
+use crate::errors::{ConfigValidationError, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// `--config` 指定的 TOML 配置文件。
+///
+/// 键名与命令行参数一致（下划线分隔），所有键均可省略；
+/// 未知的键会直接报错，避免拼写错误被静默忽略。
+///
+/// ```toml
+/// base_url = ["https://gewe.internal", "https://www.geweapi.com"]
+/// token = "00000000-0000-0000-0000-000000000000"
+/// app_id = "wx_xxx"
+/// wxid = "12345678@chatroom"
+/// at_list = ["wxid_a", "wxid_b"]
+///
+/// [recipients.ops]
+/// wxid = "87654321@chatroom"
+/// at_list = ["all"]
+///
+/// [[routes]]
+/// name = "failures"
+/// severity = ["failure", "critical"]
+/// recipients = ["ops"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    /// 单个地址或按优先级排列的地址列表。
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub base_url: Option<Vec<String>>,
+    pub token: Option<String>,
+    pub token_file: Option<PathBuf>,
+    pub token_command: Option<String>,
+    pub app_id: Option<String>,
+    pub wxid: Option<String>,
+    pub at_list: Option<Vec<String>>,
+    pub allowed_recipients: Option<Vec<String>>,
+    pub max_message_length: Option<usize>,
+    pub request_timeout: Option<String>,
+    pub send_timeout: Option<String>,
+    pub max_attempts: Option<u32>,
+    pub retry_initial_delay: Option<String>,
+    pub retry_max_delay: Option<String>,
+    pub retry_multiplier: Option<f64>,
+    pub max_concurrency: Option<usize>,
+    pub failback_interval: Option<String>,
+    pub proxy: Option<String>,
+    pub ca_cert: Option<PathBuf>,
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+    pub insecure: Option<bool>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub quiet_hours: Option<Vec<String>>,
+    pub quiet_hours_timezone: Option<String>,
+    pub quiet_hours_mode: Option<String>,
+    pub exec_success_template: Option<String>,
+    pub exec_failure_template: Option<String>,
+    pub exec_tail_lines: Option<usize>,
+    pub recipients: Option<BTreeMap<String, RecipientProfile>>,
+    pub routes: Option<Vec<RouteProfile>>,
+}
+
+/// `[recipients.<别名>]` 定义的具名接收者。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecipientProfile {
+    pub wxid: String,
+    pub at_list: Option<Vec<String>>,
+}
+
+/// `[[routes]]` 定义的路由规则，按书写顺序匹配。
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteProfile {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub severity: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub source: Vec<String>,
+    pub recipients: Vec<String>,
+    pub at_list: Option<Vec<String>>,
+}
+
+/// 同时接受 `key = "a"` 与 `key = ["a", "b"]` 两种写法。
+fn one_or_many<'de, D>(deserializer: D) -> std::result::Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(Some(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    }))
+}
+
+impl FileConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| ConfigValidationError::FileRead {
+                path: path.display().to_string(),
+                reason: e.to_string(),
+            })?;
+        Self::from_toml(path, &content)
+    }
+
+    /// 解析配置文件内容。文件中的相对路径 (`token_file`、`ca_cert`、`client_cert`、
+    /// `client_key`) 相对于配置文件所在目录，而不是进程的工作目录。
+    pub fn from_toml(path: &Path, content: &str) -> Result<Self> {
+        let mut file: Self =
+            toml::from_str(content).map_err(|e| ConfigValidationError::FileParse {
+                path: path.display().to_string(),
+                reason: e.to_string().trim_end().to_string(),
+            })?;
+        if let Some(dir) = path.parent() {
+            for slot in [
+                &mut file.token_file,
+                &mut file.ca_cert,
+                &mut file.client_cert,
+                &mut file.client_key,
+            ] {
+                if let Some(relative) = slot.as_mut().filter(|path| path.is_relative()) {
+                    *relative = dir.join(&*relative);
+                }
+            }
+        }
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_keys() {
+        let file = FileConfig::from_toml(
+            Path::new("gewe.toml"),
+            "wxid = \"ops@chatroom\"\nat_list = [\"all\"]\nmax_message_length = 500\n",
+        )
+        .expect("valid file");
+
+        assert_eq!(file.wxid.as_deref(), Some("ops@chatroom"));
+        assert_eq!(file.at_list, Some(vec!["all".to_string()]));
+        assert_eq!(file.max_message_length, Some(500));
+        assert!(file.token.is_none());
+    }
+
+    #[test]
+    fn parses_recipient_profiles() {
+        let file = FileConfig::from_toml(
+            Path::new("gewe.toml"),
+            "[recipients.ops]\nwxid = \"ops@chatroom\"\nat_list = [\"all\"]\n\n[recipients.me]\nwxid = \"wxid_me\"\n",
+        )
+        .expect("valid file");
+
+        let recipients = file.recipients.expect("recipients table");
+        assert_eq!(recipients.keys().collect::<Vec<_>>(), vec!["me", "ops"]);
+        assert_eq!(recipients["ops"].at_list, Some(vec!["all".to_string()]));
+        assert!(recipients["me"].at_list.is_none());
+    }
+
+    #[test]
+    fn base_url_accepts_string_or_list() {
+        let file = FileConfig::from_toml(Path::new("gewe.toml"), "base_url = \"https://a\"\n")
+            .expect("single url");
+        assert_eq!(file.base_url, Some(vec!["https://a".to_string()]));
+
+        let file = FileConfig::from_toml(
+            Path::new("gewe.toml"),
+            "base_url = [\"https://a\", \"https://b\"]\n",
+        )
+        .expect("url list");
+        assert_eq!(
+            file.base_url,
+            Some(vec!["https://a".to_string(), "https://b".to_string()])
+        );
+    }
+
+    #[test]
+    fn relative_paths_resolve_against_config_dir() {
+        let file = FileConfig::from_toml(
+            Path::new("/etc/gewe-notice/config.toml"),
+            "token_file = \"token\"\nca_cert = \"certs/ca.pem\"\nclient_key = \"/srv/client.key\"\n",
+        )
+        .expect("valid file");
+
+        assert_eq!(
+            file.token_file,
+            Some(PathBuf::from("/etc/gewe-notice/token"))
+        );
+        assert_eq!(
+            file.ca_cert,
+            Some(PathBuf::from("/etc/gewe-notice/certs/ca.pem"))
+        );
+        assert_eq!(file.client_key, Some(PathBuf::from("/srv/client.key")));
+        assert!(file.client_cert.is_none());
+    }
+
+    #[test]
+    fn unknown_key_names_file_and_key() {
+        let err = FileConfig::from_toml(Path::new("gewe.toml"), "wx_id = \"oops\"\n")
+            .expect_err("unknown key");
+
+        let message = err.to_string();
+        assert!(message.contains("gewe.toml"), "{message}");
+        assert!(message.contains("wx_id"), "{message}");
+    }
+}
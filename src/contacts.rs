@@ -0,0 +1,109 @@
+use crate::config::{Config, RawConfig};
+use crate::errors::Result;
+use crate::gewe_api::{Contact, ContactKind, GeweApiClient};
+use crate::render;
+
+/// 通讯录筛选条件，`contacts` 子命令与 `list_contacts` 工具共用。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContactFilter {
+    /// 只保留好友或群聊。
+    pub kind: Option<ContactKind>,
+    /// 在 wxid、昵称与备注中查找的关键字，不区分大小写。
+    pub query: Option<String>,
+}
+
+impl ContactFilter {
+    pub fn matches(&self, contact: &Contact) -> bool {
+        if self.kind.is_some_and(|kind| kind != contact.kind) {
+            return false;
+        }
+        let Some(query) = self
+            .query
+            .as_deref()
+            .map(str::trim)
+            .filter(|query| !query.is_empty())
+        else {
+            return true;
+        };
+        let query = query.to_lowercase();
+        [
+            Some(contact.wxid.as_str()),
+            contact.nickname.as_deref(),
+            contact.remark.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|field| field.to_lowercase().contains(&query))
+    }
+
+    pub fn apply(&self, contacts: Vec<Contact>) -> Vec<Contact> {
+        contacts
+            .into_iter()
+            .filter(|contact| self.matches(contact))
+            .collect()
+    }
+}
+
+/// 渲染为终端表格。
+pub fn render_table(contacts: &[Contact]) -> String {
+    let rows: Vec<Vec<String>> = contacts
+        .iter()
+        .map(|contact| {
+            vec![
+                contact.kind.label().to_string(),
+                contact.nickname.clone().unwrap_or_default(),
+                contact.remark.clone().unwrap_or_default(),
+                contact.wxid.clone(),
+            ]
+        })
+        .collect();
+    render::table(&["类型", "昵称", "备注", "wxid"], &rows)
+}
+
+/// `contacts` 子命令：读取通讯录并按条件筛选。
+pub async fn run(raw: RawConfig, filter: &ContactFilter) -> Result<Vec<Contact>> {
+    let client = GeweApiClient::new(Config::load_for_lookup(raw)?)?;
+    Ok(filter.apply(client.list_contacts().await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(wxid: &str, kind: ContactKind, nickname: &str, remark: Option<&str>) -> Contact {
+        Contact {
+            wxid: wxid.into(),
+            kind,
+            nickname: Some(nickname.into()),
+            remark: remark.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn filter_matches_kind_and_query() {
+        let contacts = vec![
+            contact("wxid_alice", ContactKind::Friend, "Alice", Some("运维 小A")),
+            contact("wxid_bob", ContactKind::Friend, "Bob", None),
+            contact("123@chatroom", ContactKind::Chatroom, "运维告警群", None),
+        ];
+
+        let by_remark = ContactFilter {
+            kind: None,
+            query: Some("运维".into()),
+        };
+        let wxids: Vec<_> = by_remark
+            .apply(contacts.clone())
+            .into_iter()
+            .map(|c| c.wxid)
+            .collect();
+        assert_eq!(wxids, vec!["wxid_alice", "123@chatroom"]);
+
+        let friends = ContactFilter {
+            kind: Some(ContactKind::Friend),
+            query: Some("BOB".into()),
+        };
+        assert_eq!(friends.apply(contacts.clone()).len(), 1);
+
+        assert_eq!(ContactFilter::default().apply(contacts).len(), 3);
+    }
+}
@@ -0,0 +1,314 @@
+use std::fmt;
+
+use crate::config::{Config, RawConfig, Recipient};
+use crate::errors::GeweNoticeError;
+use crate::gewe_api::GeweApiClient;
+
+/// 单项检查的结果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Pass => "通过",
+            Self::Warn => "警告",
+            Self::Fail => "失败",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub status: CheckStatus,
+    pub name: String,
+    pub detail: String,
+    /// 检查未通过时的修复建议。
+    pub hint: Option<String>,
+}
+
+/// `doctor` 子命令的检查报告。
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    fn push(
+        &mut self,
+        status: CheckStatus,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        hint: Option<&str>,
+    ) {
+        self.checks.push(Check {
+            status,
+            name: name.into(),
+            detail: detail.into(),
+            hint: hint.map(str::to_string),
+        });
+    }
+
+    fn pass(&mut self, name: impl Into<String>, detail: impl Into<String>) {
+        self.push(CheckStatus::Pass, name, detail, None);
+    }
+
+    fn error(&mut self, name: impl Into<String>, err: &GeweNoticeError) {
+        self.push(CheckStatus::Fail, name, err.to_string(), hint(err));
+    }
+
+    fn count(&self, status: CheckStatus) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.count(CheckStatus::Fail) > 0
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(
+                f,
+                "[{}] {}: {}",
+                check.status.label(),
+                check.name,
+                check.detail
+            )?;
+            if let Some(hint) = &check.hint {
+                writeln!(f, "       建议: {}", hint)?;
+            }
+        }
+
+        let failed = self.count(CheckStatus::Fail);
+        let warned = self.count(CheckStatus::Warn);
+        writeln!(f)?;
+        if failed == 0 && warned == 0 {
+            writeln!(f, "全部 {} 项检查通过。", self.checks.len())
+        } else {
+            writeln!(f, "{} 项失败，{} 项警告。", failed, warned)
+        }
+    }
+}
+
+/// 按错误码给出修复建议。
+fn hint(err: &GeweNoticeError) -> Option<&'static str> {
+    let hint = match err.code() {
+        "config_invalid" => "检查命令行参数、GEWE_NOTICE_* 环境变量或 --config 配置文件中对应的键",
+        "bot_offline" => "在 Gewe 管理后台重新扫码登录该微信账号",
+        "unauthorized" => "检查 token 是否正确、是否已过期；token 与 app_id 需属于同一账号",
+        "api_error" => "确认 app_id 正确且与 token 属于同一账号",
+        "network_timeout" => "检查网络连通性；网络较慢时可调大 request_timeout",
+        "network_connection_refused" | "network_dns" | "network_error" => {
+            "检查 base_url 是否正确，以及网络、防火墙与代理设置 (proxy 或 HTTPS_PROXY)"
+        }
+        "network_tls" => "内部 CA 签发的证书可通过 ca_cert 信任；仅测试环境可使用 insecure",
+        "network_http" | "invalid_response" => "确认 base_url 指向 Gewe API 而不是其他服务",
+        "not_in_group" | "chatroom_missing" => {
+            "确认机器人账号仍在该群内，且群聊 wxid 正确 (以 @chatroom 结尾)"
+        }
+        _ => return None,
+    };
+    Some(hint)
+}
+
+/// 依次执行配置校验、在线检查，以及每个群聊接收者的成员与 @ 权限检查。
+/// 前置检查失败时跳过依赖它的后续检查。
+pub async fn run(raw: RawConfig) -> Report {
+    let mut report = Report::default();
+
+    let config = match Config::load(raw) {
+        Ok(config) => config,
+        Err(e) => {
+            report.error("配置校验", &e);
+            return report;
+        }
+    };
+    report.pass(
+        "配置校验",
+        match &config.config_file {
+            Some(path) => format!("配置有效 (含配置文件 {})", path.display()),
+            None => "配置有效".to_string(),
+        },
+    );
+
+    let client = match GeweApiClient::new(config.clone()) {
+        Ok(client) => client,
+        Err(e) => {
+            report.error("HTTP 客户端", &e);
+            return report;
+        }
+    };
+
+    if let Err(e) = client.check_online().await {
+        report.error("机器人在线", &e);
+        return report;
+    }
+    let endpoint = client
+        .active_endpoint()
+        .unwrap_or_else(|| config.primary_base_url().to_string());
+    report.pass("机器人在线", format!("Gewe 地址 {}", endpoint));
+
+    let mut targets = vec![("默认接收者".to_string(), config.default_recipient())];
+    targets.extend(
+        config
+            .recipients
+            .iter()
+            .map(|(alias, recipient)| (format!("接收者 {}", alias), recipient.clone())),
+    );
+
+    let mut own_wxid = None;
+    for (label, recipient) in targets {
+        check_recipient(&client, &mut report, &label, &recipient, &mut own_wxid).await;
+    }
+
+    report
+}
+
+async fn check_recipient(
+    client: &GeweApiClient,
+    report: &mut Report,
+    label: &str,
+    recipient: &Recipient,
+    own_wxid: &mut Option<String>,
+) {
+    let name = format!("{} {}", label, recipient.wxid);
+    let at_list = recipient.normalized_at_list().unwrap_or_default();
+
+    if !recipient.is_chatroom() {
+        if at_list.is_empty() {
+            report.pass(name, "个人私聊");
+        } else {
+            report.push(
+                CheckStatus::Warn,
+                name,
+                "at_list 只对群聊生效，发送给个人时会被忽略",
+                Some("从该接收者的配置中移除 at_list"),
+            );
+        }
+        return;
+    }
+
+    let members = match client.chatroom_members(recipient.wxid.as_str()).await {
+        Ok(members) => members,
+        Err(e) => {
+            report.error(name, &e);
+            return;
+        }
+    };
+    report.pass(
+        name.clone(),
+        format!("机器人在群内，共 {} 名成员", members.len()),
+    );
+
+    if at_list.is_empty() {
+        return;
+    }
+
+    let at_name = format!("{} 的 @ 列表", label);
+    // 与发送时的判断一致：只有单独的 all 表示 @所有人，混用时 all 按普通 wxid 检查
+    if recipient.mentions_all() {
+        if own_wxid.is_none() {
+            *own_wxid = client.own_wxid().await.ok();
+        }
+        match own_wxid.as_deref() {
+            Some(wxid)
+                if members
+                    .iter()
+                    .any(|member| member.wxid == wxid && member.can_mention_all()) =>
+            {
+                report.pass(at_name, "机器人是群主或管理员，可以 @所有人");
+            }
+            Some(_) => report.push(
+                CheckStatus::Warn,
+                at_name,
+                "机器人不是群主或管理员，@所有人 会降级为不带 @ 的普通消息",
+                Some("请群主将机器人设为管理员，或改为 @ 具体成员"),
+            ),
+            None => report.push(
+                CheckStatus::Warn,
+                at_name,
+                "无法获取机器人自身的 wxid，未能确认 @所有人 权限",
+                None,
+            ),
+        }
+        return;
+    }
+
+    let missing: Vec<&str> = at_list
+        .iter()
+        .filter(|wxid| !members.iter().any(|m| &m.wxid == *wxid))
+        .map(String::as_str)
+        .collect();
+    if missing.is_empty() {
+        report.pass(at_name, format!("{} 名成员都在群内", at_list.len()));
+    } else if missing.contains(&"all") {
+        report.push(
+            CheckStatus::Fail,
+            at_name,
+            format!("以下 wxid 不在群内: {}", missing.join(", ")),
+            Some("all 只能单独使用才表示 @所有人，与其他 wxid 混用时会被当作普通 wxid"),
+        );
+    } else {
+        report.push(
+            CheckStatus::Fail,
+            at_name,
+            format!("以下 wxid 不在群内: {}", missing.join(", ")),
+            Some("从 at_list 中移除这些 wxid，或确认其拼写与群成员的 wxid 一致"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ApiBusinessError;
+
+    #[test]
+    fn report_summarises_failures_and_hints() {
+        let mut report = Report::default();
+        report.pass("配置校验", "配置有效");
+        assert!(!report.has_failures());
+        assert!(report.to_string().contains("全部 1 项检查通过"));
+
+        report.error("机器人在线", &ApiBusinessError::BotOffline.into());
+        report.push(CheckStatus::Warn, "@ 列表", "无法确认", None);
+        assert!(report.has_failures());
+
+        let text = report.to_string();
+        assert!(text.contains("[失败] 机器人在线"), "{text}");
+        assert!(
+            text.contains("建议: 在 Gewe 管理后台重新扫码登录"),
+            "{text}"
+        );
+        assert!(text.contains("1 项失败，1 项警告"), "{text}");
+    }
+
+    #[tokio::test]
+    async fn invalid_config_stops_after_validation() {
+        use clap::Parser;
+
+        let raw = RawConfig::parse_from([
+            "gewe-notice-mcp",
+            "--token",
+            "00000000-0000-0000-0000-000000000000",
+            "--app-id",
+            "not-an-app-id",
+            "--wxid",
+            "wxid_me",
+        ]);
+        let report = run(raw).await;
+
+        assert_eq!(report.checks.len(), 1);
+        assert_eq!(report.checks[0].status, CheckStatus::Fail);
+        assert!(report.checks[0].hint.is_some());
+    }
+}
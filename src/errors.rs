@@ -11,6 +11,53 @@ pub enum ConfigValidationError {
 
     #[error("WxId格式无效: {reason}")]
     InvalidWxId { reason: WxIdValidationError },
+
+    #[error("消息长度上限无效: {value} (至少为 {min})")]
+    InvalidMaxLength { value: usize, min: usize },
+
+    #[error("接收者别名无效: '{alias}' (只能包含字母、数字、'-' 和 '_')")]
+    InvalidAlias { alias: String },
+
+    #[error("接收者 `{alias}` 配置无效: {reason}")]
+    InvalidRecipient {
+        alias: String,
+        reason: Box<ConfigValidationError>,
+    },
+
+    #[error("路由规则 '{rule}' 无效: {reason}")]
+    InvalidRoute { rule: String, reason: String },
+
+    #[error("token、token_file、token_command 只能指定其中一个")]
+    ConflictingTokenSources,
+
+    #[error("无法从{origin}读取 Token: {reason}")]
+    TokenSourceFailed { origin: String, reason: String },
+
+    #[error("配置项 {key} 无效: '{value}' ({reason})")]
+    InvalidValue {
+        key: &'static str,
+        value: String,
+        reason: String,
+    },
+
+    #[error("缺少必填配置 {key}: 请通过命令行参数、环境变量 {env} 或 --config 配置文件提供")]
+    Missing {
+        key: &'static str,
+        env: &'static str,
+    },
+
+    #[error("无法读取配置文件 {path}: {reason}")]
+    FileRead { path: String, reason: String },
+
+    #[error("配置文件 {path} 解析失败: {reason}")]
+    FileParse { path: String, reason: String },
+
+    #[error("配置文件 {path} 中的 `{key}` 无效: {reason}")]
+    InFile {
+        path: String,
+        key: &'static str,
+        reason: Box<ConfigValidationError>,
+    },
 }
 
 #[derive(Error, Debug, Clone)]
@@ -39,11 +86,14 @@ pub enum NetworkError {
     #[error("连接被拒绝")]
     ConnectionRefused,
 
+    #[error("连接超时，地址可能不可达")]
+    ConnectTimeout,
+
     #[error("DNS解析失败: {host}")]
     DnsResolution { host: String },
 
-    #[error("TLS/SSL错误")]
-    TlsError,
+    #[error("TLS/SSL错误: {detail}")]
+    TlsError { detail: String },
 
     #[error("HTTP错误 (状态码: {status}, 响应: {body:?})")]
     HttpError { status: u16, body: Option<String> },
@@ -52,12 +102,19 @@ pub enum NetworkError {
     Underlying(String),
 }
 
-impl From<reqwest::Error> for NetworkError {
-    fn from(err: reqwest::Error) -> Self {
-        if err.is_timeout() {
-            NetworkError::Timeout {
-                duration: Duration::from_secs(10),
+impl NetworkError {
+    /// 转换 reqwest 错误。`timeout` 为该请求实际配置的超时时间，用于超时错误的提示。
+    pub fn from_reqwest(err: reqwest::Error, timeout: Duration) -> Self {
+        // TLS 握手失败在 reqwest 中也属于连接错误，需要先于 is_connect 判断
+        if let Some(tls) = find_tls_error(&err) {
+            NetworkError::TlsError {
+                detail: describe_tls_error(tls),
             }
+        } else if err.is_connect() && err.is_timeout() {
+            // 建立连接超时：请求没有到达服务端，与请求处理超时区分开
+            NetworkError::ConnectTimeout
+        } else if err.is_timeout() {
+            NetworkError::Timeout { duration: timeout }
         } else if err.is_connect() {
             NetworkError::ConnectionRefused
         } else if let Some(url) = err.url() {
@@ -67,11 +124,6 @@ impl From<reqwest::Error> for NetworkError {
                 NetworkError::DnsResolution {
                     host: url.host_str().unwrap_or("unknown").to_string(),
                 }
-            } else if error_str.contains("ssl")
-                || error_str.contains("tls")
-                || error_str.contains("certificate")
-            {
-                NetworkError::TlsError
             } else {
                 NetworkError::Underlying(error_str)
             }
@@ -81,6 +133,57 @@ impl From<reqwest::Error> for NetworkError {
     }
 }
 
+/// 在错误链中查找 rustls 错误。hyper 会把它层层包装在 `io::Error` 中，
+/// 而 `io::Error::source` 会跳过被包装的错误本身，因此需要用 `get_ref` 逐层取出。
+fn find_tls_error<'a>(err: &'a (dyn std::error::Error + 'static)) -> Option<&'a rustls::Error> {
+    let mut current = Some(err);
+    while let Some(err) = current {
+        if let Some(tls) = err.downcast_ref::<rustls::Error>() {
+            return Some(tls);
+        }
+        current = match err.downcast_ref::<std::io::Error>() {
+            Some(io) => io
+                .get_ref()
+                .map(|inner| inner as &(dyn std::error::Error + 'static)),
+            None => err.source(),
+        };
+    }
+    None
+}
+
+fn describe_tls_error(err: &rustls::Error) -> String {
+    use rustls::CertificateError;
+
+    let hint = match err {
+        rustls::Error::InvalidCertificate(CertificateError::UnknownIssuer) => {
+            "服务器证书由未知 CA 签发，可通过 ca_cert 信任内部 CA"
+        }
+        rustls::Error::InvalidCertificate(CertificateError::NotValidForName) => {
+            "服务器证书与访问的域名不匹配，请检查 base_url"
+        }
+        rustls::Error::InvalidCertificate(CertificateError::Expired) => "服务器证书已过期",
+        rustls::Error::InvalidCertificate(_) => "服务器证书无效",
+        rustls::Error::InvalidMessage(_) => {
+            "服务器未使用 TLS，请确认 base_url 应为 http 还是 https"
+        }
+        rustls::Error::AlertReceived(_) => "服务器拒绝了握手，可能需要配置 client_cert 客户端证书",
+        _ => return err.to_string(),
+    };
+    format!("{} ({})", hint, err)
+}
+
+/// 拼接错误及其全部来源，reqwest 的顶层错误信息通常过于笼统。
+pub(crate) fn error_chain(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
+}
+
 #[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ApiErrorCode {
     #[error("不在群内")]
@@ -121,6 +224,24 @@ pub enum ApiBusinessError {
 
     #[error("请求被限流 (重试时间: {retry_after:?})")]
     RateLimited { retry_after: Option<Duration> },
+
+    #[error("Token 无效或已过期")]
+    Unauthorized,
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum AttachmentError {
+    #[error("文件名缺少扩展名: {file_name}")]
+    MissingExtension { file_name: String },
+
+    #[error("不支持的文件类型 '.{extension}': {file_name}")]
+    UnsupportedExtension {
+        file_name: String,
+        extension: String,
+    },
+
+    #[error("文件过大: {size} 字节，超过上限 {limit} 字节")]
+    TooLarge { size: u64, limit: u64 },
 }
 
 #[derive(Error, Debug, Clone)]
@@ -134,9 +255,22 @@ pub enum GeweNoticeError {
     #[error("业务逻辑错误: {0}")]
     Business(#[from] ApiBusinessError),
 
+    #[error("附件校验失败: {0}")]
+    Attachment(#[from] AttachmentError),
+
     #[error("JSON解析错误: {0}")]
     Json(String),
 
+    #[error("第 {part}/{total} 段发送失败: {source}")]
+    PartialDelivery {
+        part: usize,
+        total: usize,
+        source: Box<GeweNoticeError>,
+    },
+
+    #[error("未找到可撤回的消息: {0}")]
+    UnknownMessage(String),
+
     #[error("任务被取消")]
     Cancelled,
 }
@@ -148,26 +282,81 @@ impl From<serde_json::Error> for GeweNoticeError {
 }
 
 impl GeweNoticeError {
+    /// 临时故障，原样重试可能成功。部分分段已送达时不可重试：重发整条消息
+    /// 会让已送达的分段重复出现。
     pub fn is_retryable(&self) -> bool {
         matches!(
             self,
             Self::Network(NetworkError::Timeout { .. })
                 | Self::Network(NetworkError::ConnectionRefused)
+                | Self::Network(NetworkError::ConnectTimeout)
                 | Self::Business(ApiBusinessError::RateLimited { .. })
         )
     }
 
     pub fn is_fatal(&self) -> bool {
+        if let Self::PartialDelivery { source, .. } = self {
+            return source.is_fatal();
+        }
+
         matches!(
             self,
             Self::Config(_)
+                | Self::Network(NetworkError::TlsError { .. })
                 | Self::Business(ApiBusinessError::BotOffline)
+                | Self::Business(ApiBusinessError::Unauthorized)
                 | Self::Business(ApiBusinessError::KnownError {
                     code: ApiErrorCode::NotInGroup | ApiErrorCode::ChatroomMissing
                 })
         )
     }
 
+    /// 稳定的机器可读错误码，MCP 客户端可据此决定重试还是放弃。
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(_) => "config_invalid",
+            Self::Network(err) => match err {
+                NetworkError::Timeout { .. } => "network_timeout",
+                NetworkError::ConnectionRefused => "network_connection_refused",
+                NetworkError::ConnectTimeout => "network_connect_timeout",
+                NetworkError::DnsResolution { .. } => "network_dns",
+                NetworkError::TlsError { .. } => "network_tls",
+                NetworkError::HttpError { .. } => "network_http",
+                NetworkError::Underlying(_) => "network_error",
+            },
+            Self::Business(err) => match err {
+                ApiBusinessError::BotOffline => "bot_offline",
+                ApiBusinessError::KnownError { code } => match code {
+                    ApiErrorCode::NotInGroup => "not_in_group",
+                    ApiErrorCode::ChatroomMissing => "chatroom_missing",
+                    ApiErrorCode::PermissionDenied => "permission_denied",
+                },
+                ApiBusinessError::UnknownError { .. } => "api_error",
+                ApiBusinessError::RateLimited { .. } => "rate_limited",
+                ApiBusinessError::Unauthorized => "unauthorized",
+            },
+            Self::Attachment(err) => match err {
+                AttachmentError::MissingExtension { .. } => "attachment_missing_extension",
+                AttachmentError::UnsupportedExtension { .. } => "attachment_unsupported_extension",
+                AttachmentError::TooLarge { .. } => "attachment_too_large",
+            },
+            Self::Json(_) => "invalid_response",
+            Self::PartialDelivery { .. } => "partial_delivery",
+            Self::UnknownMessage(_) => "unknown_message",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Gewe 返回的业务错误码 (若有)。
+    pub fn api_code(&self) -> Option<i32> {
+        match self {
+            Self::Business(ApiBusinessError::KnownError { code }) => Some(code.as_i32()),
+            Self::Business(ApiBusinessError::UnknownError { code, .. }) => Some(*code),
+            Self::PartialDelivery { source, .. } => source.api_code(),
+            _ => None,
+        }
+    }
+
     pub fn retry_after(&self) -> Option<Duration> {
         match self {
             Self::Business(ApiBusinessError::RateLimited { retry_after }) => *retry_after,
@@ -0,0 +1,540 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::config::{Config, RawConfig};
+use crate::gewe_api::GeweApiClient;
+use crate::send;
+use crate::watch::MAX_LINE_LEN;
+
+/// 通知中默认附带的输出行数。
+pub const DEFAULT_TAIL_LINES: usize = 20;
+
+pub const DEFAULT_SUCCESS_TEMPLATE: &str =
+    "✅ 命令执行成功\n\n命令: {command}\n主机: {hostname}\n耗时: {duration}\n结果: {status}\n\n最后输出:\n{output}";
+
+pub const DEFAULT_FAILURE_TEMPLATE: &str =
+    "❌ 命令执行失败\n\n命令: {command}\n主机: {hostname}\n耗时: {duration}\n结果: {status}\n\n最后输出:\n{output}";
+
+/// 命令无法启动时的退出码，与 shell 的约定一致。
+const EXIT_NOT_EXECUTABLE: i32 = 126;
+const EXIT_NOT_FOUND: i32 = 127;
+
+/// 模板中可用的占位符。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Command,
+    Status,
+    ExitCode,
+    Duration,
+    Hostname,
+    Output,
+}
+
+impl Field {
+    const ALL: [Field; 6] = [
+        Field::Command,
+        Field::Status,
+        Field::ExitCode,
+        Field::Duration,
+        Field::Hostname,
+        Field::Output,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Command => "command",
+            Self::Status => "status",
+            Self::ExitCode => "exit_code",
+            Self::Duration => "duration",
+            Self::Hostname => "hostname",
+            Self::Output => "output",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// `exec` 子命令的通知模板，使用 `{command}`、`{status}`、`{exit_code}`、`{duration}`、
+/// `{hostname}`、`{output}` 占位符，`{{` 与 `}}` 表示字面的花括号。加载配置时校验。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("占位符 '{{{}' 缺少 '}}'", name)),
+                        }
+                    }
+                    let field = Field::ALL
+                        .into_iter()
+                        .find(|field| field.name() == name.trim())
+                        .ok_or_else(|| {
+                            format!(
+                                "未知占位符 '{{{}}}'，可用: {}",
+                                name,
+                                Field::ALL
+                                    .map(|field| format!("{{{}}}", field.name()))
+                                    .join(", ")
+                            )
+                        })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+                '}' => return Err("单独的 '}' 需要写成 '}}'".to_string()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => {
+                    f.write_str(&text.replace('{', "{{").replace('}', "}}"))?
+                }
+                Segment::Field(field) => write!(f, "{{{}}}", field.name())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Template {
+    pub fn render(&self, report: &ExecReport) -> String {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Field(field) => rendered.push_str(&match field {
+                    Field::Command => report.command.clone(),
+                    Field::Status => report.status_text(),
+                    Field::ExitCode => report.exit_code().to_string(),
+                    Field::Duration => format_duration(report.duration),
+                    Field::Hostname => report.hostname.clone(),
+                    Field::Output if report.output.is_empty() => "(无输出)".to_string(),
+                    Field::Output => report.output.join("\n"),
+                }),
+            }
+        }
+        rendered
+    }
+}
+
+/// `exec` 子命令的通知设置。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecSettings {
+    pub success_template: Template,
+    pub failure_template: Template,
+    /// 通知中附带的最后输出行数，0 表示不附带。
+    pub tail_lines: usize,
+}
+
+impl Default for ExecSettings {
+    fn default() -> Self {
+        Self {
+            success_template: DEFAULT_SUCCESS_TEMPLATE.parse().expect("valid template"),
+            failure_template: DEFAULT_FAILURE_TEMPLATE.parse().expect("valid template"),
+            tail_lines: DEFAULT_TAIL_LINES,
+        }
+    }
+}
+
+/// 被包装命令的执行结果。
+#[derive(Debug, Clone)]
+pub struct ExecReport {
+    /// 便于复制执行的命令行，含空白或引号的参数会加上引号。
+    pub command: String,
+    /// 正常退出时的退出码。
+    pub code: Option<i32>,
+    /// 被信号终止时的信号编号。
+    pub signal: Option<i32>,
+    pub duration: Duration,
+    pub hostname: String,
+    /// stdout 与 stderr 合并后的最后若干行，已去除终端控制序列。
+    pub output: Vec<String>,
+}
+
+impl ExecReport {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// 返回给调用方的退出码；被信号终止时按 shell 约定为 128 + 信号编号。
+    pub fn exit_code(&self) -> i32 {
+        match (self.code, self.signal) {
+            (Some(code), _) => code,
+            (None, Some(signal)) => 128 + signal,
+            (None, None) => 1,
+        }
+    }
+
+    pub fn status_text(&self) -> String {
+        match (self.code, self.signal) {
+            (Some(code), _) => format!("退出码 {}", code),
+            (None, Some(signal)) => format!("被信号 {} 终止", signal),
+            (None, None) => "异常退出".to_string(),
+        }
+    }
+
+    pub fn render(&self, settings: &ExecSettings) -> String {
+        if self.success() {
+            settings.success_template.render(self)
+        } else {
+            settings.failure_template.render(self)
+        }
+    }
+}
+
+/// 按 shell 的写法拼接命令行。
+pub fn command_line(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "-_./=:,+@%".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{:.1} 秒", duration.as_secs_f64()),
+        60..=3599 => format!("{} 分 {} 秒", secs / 60, secs % 60),
+        _ => format!(
+            "{} 小时 {} 分 {} 秒",
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        ),
+    }
+}
+
+/// 去掉颜色等 ANSI 控制序列，避免在微信中显示为乱码。
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            stripped.push(c);
+            continue;
+        }
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    stripped
+}
+
+/// 保留最后若干行输出，stdout 与 stderr 共用。
+#[derive(Debug, Clone)]
+struct TailBuffer {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl TailBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    fn push(&self, raw: &[u8]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let text = String::from_utf8_lossy(raw);
+        // 进度条等以 '\r' 覆盖同一行，只保留最终显示的内容
+        let text = text.trim_end_matches(['\r', '\n']);
+        let visible = text.rsplit('\r').next().unwrap_or_default();
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(strip_ansi(visible));
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().cloned().collect()
+    }
+}
+
+/// 将子进程输出原样转发到当前进程，同时按行记录到 `tail`。
+async fn forward(
+    mut reader: impl AsyncRead + Unpin,
+    mut writer: impl AsyncWrite + Unpin,
+    tail: TailBuffer,
+) {
+    let mut buf = [0u8; 8192];
+    let mut partial = Vec::new();
+    loop {
+        let n = match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        let _ = writer.write_all(&buf[..n]).await;
+        let _ = writer.flush().await;
+
+        partial.extend_from_slice(&buf[..n]);
+        while let Some(pos) = partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = partial.drain(..=pos).collect();
+            tail.push(&line);
+        }
+        // 进度条以 '\r' 覆盖同一行而长时间不换行：只保留最后一次覆盖的内容，
+        // 仍超过上限时按 watch 的做法切分为多行
+        let overwritten = partial[..partial.len().saturating_sub(1)]
+            .iter()
+            .rposition(|&b| b == b'\r');
+        if let Some(pos) = overwritten {
+            partial.drain(..=pos);
+        }
+        while partial.len() > MAX_LINE_LEN {
+            let line: Vec<u8> = partial.drain(..MAX_LINE_LEN).collect();
+            tail.push(&line);
+        }
+    }
+    if !partial.is_empty() {
+        tail.push(&partial);
+    }
+}
+
+/// 运行命令并转发其输出，等待其结束后返回执行结果。
+pub async fn run_command(argv: &[String], tail_lines: usize) -> std::io::Result<ExecReport> {
+    let Some((program, args)) = argv.split_first() else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "缺少要执行的命令",
+        ));
+    };
+
+    let started = Instant::now();
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // 两路输出分别读取，二者交错时末尾输出中的行序不保证与终端一致。
+    let tail = TailBuffer::new(tail_lines);
+    let stdout = child
+        .stdout
+        .take()
+        .map(|stdout| tokio::spawn(forward(stdout, tokio::io::stdout(), tail.clone())));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|stderr| tokio::spawn(forward(stderr, tokio::io::stderr(), tail.clone())));
+
+    let status = child.wait().await?;
+    for task in [stdout, stderr].into_iter().flatten() {
+        let _ = task.await;
+    }
+
+    #[cfg(unix)]
+    let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+    #[cfg(not(unix))]
+    let signal = None;
+
+    Ok(ExecReport {
+        command: command_line(argv),
+        code: status.code(),
+        signal,
+        duration: started.elapsed(),
+        hostname: gethostname::gethostname().to_string_lossy().into_owned(),
+        output: tail.lines(),
+    })
+}
+
+/// `exec` 子命令：运行命令，结束后发送通知，返回命令自身的退出码。
+///
+/// 配置在启动命令之前校验，避免长时间任务结束后才发现无法通知。
+pub async fn run(raw: RawConfig, argv: &[String]) -> i32 {
+    let client = match Config::load(raw).and_then(GeweApiClient::new) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("gewe-notice-mcp: 配置错误 [{}]: {}", e.code(), e);
+            return send::exit_code(&e);
+        }
+    };
+    let settings = client.config().exec.clone();
+
+    // Ctrl-C 同时送达子进程；这里忽略它，等子进程退出后照常通知
+    tokio::spawn(async { while tokio::signal::ctrl_c().await.is_ok() {} });
+
+    let report = match run_command(argv, settings.tail_lines).await {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("gewe-notice-mcp: 无法执行 {}: {}", command_line(argv), e);
+            return match e.kind() {
+                std::io::ErrorKind::NotFound => EXIT_NOT_FOUND,
+                _ => EXIT_NOT_EXECUTABLE,
+            };
+        }
+    };
+
+    if let Err(e) = client.post_text(&report.render(&settings)).await {
+        eprintln!("gewe-notice-mcp: 发送完成通知失败 [{}]: {}", e.code(), e);
+    }
+    report.exit_code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(code: Option<i32>, signal: Option<i32>) -> ExecReport {
+        ExecReport {
+            command: command_line(&["make".into(), "release build".into()]),
+            code,
+            signal,
+            duration: Duration::from_secs(2 * 3600 + 3 * 60 + 4),
+            hostname: "ci-01".into(),
+            output: vec!["done".into()],
+        }
+    }
+
+    #[test]
+    fn templates_are_validated_and_rendered() {
+        let template: Template = "{{{hostname}}} {command}: {status} ({exit_code}) {duration}"
+            .parse()
+            .expect("valid template");
+        assert_eq!(
+            template.render(&report(Some(2), None)),
+            "{ci-01} make 'release build': 退出码 2 (2) 2 小时 3 分 4 秒"
+        );
+        assert_eq!(
+            template.to_string(),
+            "{{{hostname}}} {command}: {status} ({exit_code}) {duration}"
+        );
+
+        assert!("{host}"
+            .parse::<Template>()
+            .unwrap_err()
+            .contains("{hostname}"));
+        assert!("{output".parse::<Template>().is_err());
+        assert!("a } b".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn report_picks_template_and_exit_code() {
+        let settings = ExecSettings::default();
+        let ok = report(Some(0), None);
+        assert!(ok.render(&settings).starts_with("✅"));
+        assert_eq!(ok.exit_code(), 0);
+
+        let killed = report(None, Some(9));
+        assert!(killed.render(&settings).starts_with("❌"));
+        assert!(killed.render(&settings).contains("被信号 9 终止"));
+        assert_eq!(killed.exit_code(), 137);
+    }
+
+    #[test]
+    fn tail_keeps_last_visible_lines() {
+        let tail = TailBuffer::new(2);
+        tail.push(b"first\n");
+        tail.push(b"10%\r50%\r100%\n");
+        tail.push(b"\x1b[31merror\x1b[0m: boom\r\n");
+        assert_eq!(tail.lines(), vec!["100%", "error: boom"]);
+
+        let none = TailBuffer::new(0);
+        none.push(b"ignored\n");
+        assert!(none.lines().is_empty());
+    }
+
+    #[tokio::test]
+    async fn forward_bounds_output_without_newlines() {
+        let progress: String = (0..100_000).map(|i| format!("\rprogress {i}%")).collect();
+        let output = format!("{progress}\ndone\n");
+        assert!(output.len() > MAX_LINE_LEN);
+        let tail = TailBuffer::new(5);
+        forward(output.as_bytes(), tokio::io::sink(), tail.clone()).await;
+        assert_eq!(tail.lines(), vec!["progress 99999%", "done"]);
+
+        let output = "y".repeat(MAX_LINE_LEN * 2 + 5);
+        let tail = TailBuffer::new(5);
+        forward(output.as_bytes(), tokio::io::sink(), tail.clone()).await;
+        let lengths: Vec<usize> = tail.lines().iter().map(String::len).collect();
+        assert_eq!(lengths, vec![MAX_LINE_LEN, MAX_LINE_LEN, 5]);
+    }
+
+    #[test]
+    fn durations_are_human_readable() {
+        assert_eq!(format_duration(Duration::from_millis(4_300)), "4.3 秒");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2 分 5 秒");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_command_reports_exit_code_and_output() {
+        let argv: Vec<String> = ["sh", "-c", "echo out; echo err >&2; printf partial; exit 3"]
+            .map(String::from)
+            .to_vec();
+        let report = run_command(&argv, 10).await.expect("ran");
+        assert_eq!(report.code, Some(3));
+        assert_eq!(report.exit_code(), 3);
+        assert!(report.command.starts_with("sh -c 'echo out;"));
+        let mut output = report.output.clone();
+        output.sort();
+        assert_eq!(output, vec!["err", "out", "partial"]);
+
+        let err = run_command(&["/nonexistent/binary".to_string()], 10)
+            .await
+            .expect_err("missing binary");
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,18 @@
+use tokio::task::JoinHandle;
+
+use crate::gewe_api::GeweApiClient;
+
+/// 在后台定期探测主地址。客户端因主地址不可用而切换到备用地址后，
+/// 每隔 `failback_interval` 向主地址发送一次 `checkOnline`，恢复后切回主地址。
+///
+/// 只配置了一个地址或当前已在使用主地址时，探测不会发出请求。
+pub fn spawn(client: GeweApiClient) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            // 每轮重新读取间隔，热重载后立即生效
+            let interval = client.config().http.failback_interval;
+            tokio::time::sleep(interval).await;
+            client.probe_primary().await;
+        }
+    })
+}
@@ -1,14 +1,29 @@
-use reqwest::{header, Client, StatusCode};
+use reqwest::{header, Client, Proxy, StatusCode};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::Duration;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 use tokio::time::{sleep, timeout};
 use tracing::{error, info, warn};
 
-use crate::config::Config;
-use crate::errors::{ApiBusinessError, ApiErrorCode, NetworkError, Result};
+use crate::config::{Config, Recipient, RetryPolicy};
+use crate::errors::{
+    ApiBusinessError, ApiErrorCode, AttachmentError, ConfigValidationError, GeweNoticeError,
+    NetworkError, Result,
+};
+use crate::render::split_message;
+
+/// 文件消息允许的最大体积 (100 MiB)。
+pub const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+
+/// 文件消息允许的扩展名，覆盖常见的日志、报告与归档格式。
+pub const ALLOWED_FILE_EXTENSIONS: &[&str] = &[
+    "txt", "log", "md", "json", "csv", "tsv", "xml", "yaml", "yml", "html", "pdf", "doc", "docx",
+    "xls", "xlsx", "ppt", "pptx", "png", "jpg", "jpeg", "gif", "svg", "zip", "gz", "tgz", "tar",
+    "7z",
+];
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -32,16 +47,66 @@ struct GetChatroomMemberListRequest {
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ChatroomMember {
+struct RawChatroomMember {
     wxid: String,
-    nick_name: String,
+    #[serde(default)]
+    nick_name: Option<String>,
+    #[serde(default)]
     display_name: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ChatroomMemberData {
-    member_list: Vec<ChatroomMember>,
+    member_list: Vec<RawChatroomMember>,
+    #[serde(default)]
+    chatroom_owner: Option<String>,
+    #[serde(default)]
+    admin_wxid: Option<Vec<String>>,
+}
+
+/// 群成员及其在群内的身份。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChatroomMember {
+    pub wxid: String,
+    /// 微信昵称。
+    pub nickname: String,
+    /// 在本群设置的群昵称，未设置时为 `None`。
+    pub display_name: Option<String>,
+    pub is_owner: bool,
+    pub is_admin: bool,
+}
+
+impl ChatroomMember {
+    /// 群内显示的名字，也用于 @ 前缀：优先群昵称，其次微信昵称。
+    pub fn name(&self) -> &str {
+        self.display_name
+            .as_deref()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.nickname)
+    }
+
+    /// 群主或管理员才有权限 @所有人。
+    pub fn can_mention_all(&self) -> bool {
+        self.is_owner || self.is_admin
+    }
+}
+
+impl ChatroomMemberData {
+    fn into_members(self) -> Vec<ChatroomMember> {
+        let owner = self.chatroom_owner;
+        let admins = self.admin_wxid.unwrap_or_default();
+        self.member_list
+            .into_iter()
+            .map(|member| ChatroomMember {
+                is_owner: owner.as_deref() == Some(member.wxid.as_str()),
+                is_admin: admins.contains(&member.wxid),
+                nickname: member.nick_name.unwrap_or_default(),
+                display_name: member.display_name,
+                wxid: member.wxid,
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,6 +117,117 @@ struct GetChatroomMemberListResponse {
     data: Option<ChatroomMemberData>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GetProfileRequest {
+    app_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileData {
+    wxid: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetProfileResponse {
+    ret: i32,
+    msg: String,
+    data: Option<ProfileData>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FetchContactsListRequest {
+    app_id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ContactsListData {
+    #[serde(default)]
+    friends: Vec<String>,
+    #[serde(default)]
+    chatrooms: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GetBriefInfoRequest {
+    app_id: String,
+    wxids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BriefInfo {
+    user_name: String,
+    #[serde(default)]
+    nick_name: Option<String>,
+    #[serde(default)]
+    remark: Option<String>,
+}
+
+/// Gewe 通用的 `{ret, msg, data}` 响应。
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    ret: i32,
+    msg: String,
+    data: Option<T>,
+}
+
+/// 联系人类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContactKind {
+    Friend,
+    Chatroom,
+}
+
+impl ContactKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Friend => "friend",
+            Self::Chatroom => "chatroom",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Friend => "好友",
+            Self::Chatroom => "群聊",
+        }
+    }
+}
+
+impl std::str::FromStr for ContactKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "friend" | "friends" => Ok(Self::Friend),
+            "chatroom" | "chatrooms" | "group" | "groups" => Ok(Self::Chatroom),
+            _ => Err(format!("未知联系人类型 '{}'，可选值: friend, chatroom", s)),
+        }
+    }
+}
+
+/// 通讯录中的好友或群聊。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Contact {
+    pub wxid: String,
+    pub kind: ContactKind,
+    /// 昵称；群聊为群名称。
+    pub nickname: Option<String>,
+    /// 机器人账号为该联系人设置的备注。
+    pub remark: Option<String>,
+}
+
+/// 每次 `getBriefInfo` 最多查询的 wxid 数量。
+const BRIEF_INFO_BATCH: usize = 100;
+
+/// 通讯录缓存的有效期。
+const CONTACTS_CACHE_TTL: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct PostTextRequest {
@@ -62,37 +238,157 @@ struct PostTextRequest {
     ats: Option<String>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct PostImageRequest {
+    app_id: String,
+    to_wxid: String,
+    img_url: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct PostFileRequest {
+    app_id: String,
+    to_wxid: String,
+    file_url: String,
+    file_name: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct PostLinkRequest {
+    app_id: String,
+    to_wxid: String,
+    title: String,
+    desc: String,
+    link_url: String,
+    thumb_url: String,
+}
+
+/// 链接卡片消息的内容。
+#[derive(Debug, Clone, Default)]
+pub struct LinkMessage {
+    pub title: String,
+    pub desc: String,
+    pub link_url: String,
+    pub thumb_url: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct RevokeMsgRequest {
+    app_id: String,
+    to_wxid: String,
+    msg_id: String,
+    new_msg_id: String,
+    create_time: String,
+}
+
+/// 已发送消息的种类。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageKind {
+    Text,
+    Image,
+    File,
+    Link,
+}
+
+/// 一条已发送消息的标识，撤回时需要原样回传给 Gewe。
+#[derive(Debug, Clone, Serialize)]
+pub struct SentMessage {
+    pub kind: MessageKind,
+    pub to_wxid: String,
+    pub preview: String,
+    pub msg_id: String,
+    pub new_msg_id: String,
+    pub create_time: String,
+}
+
+/// 一次发送工具调用的投递结果，同时作为 MCP 工具的结构化输出。
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    /// 实际接收者的 WXID。
+    pub recipient: String,
+    /// 实际发送的分段数量，未分段时为 1。
+    pub parts: usize,
+    /// Gewe 返回了消息标识的已发送消息，按发送顺序排列。
+    pub messages: Vec<SentMessage>,
+    /// 每条消息最终发出的内容；文本消息包含 @ 前缀与分段序号。
+    pub contents: Vec<String>,
+    /// 实际被 @ 的 WXID，@ 全体成员时为 `["all"]`。
+    pub mentioned: Vec<String>,
+    /// @ 全体成员因无权限而降级为普通消息。
+    pub at_all_fallback: bool,
+    /// 发送请求的尝试次数，包含重试。
+    pub attempts: u32,
+    /// 从开始发送到全部完成的耗时。
+    pub elapsed_ms: u64,
+    /// 处理本次发送的 Gewe 地址；配置了多个地址时可据此判断是否发生了故障切换。
+    pub endpoint: String,
+    /// 消息已送达、但附带的操作失败时的说明，例如链接卡片之后的 @ 提醒未能发送。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// 单条消息的发送结果，由 [`Delivery::record`] 汇总。
+struct SentPart {
+    message: Option<SentMessage>,
+    content: String,
+    mentioned: Vec<String>,
+    at_all_fallback: bool,
+    endpoint: String,
+}
+
 #[derive(Debug, Deserialize)]
-struct PostTextResponse {
+struct PostMessageResponse {
     ret: i32,
     msg: String,
-    data: Option<PostTextData>,
+    data: Option<PostMessageData>,
 }
 
 #[derive(Debug, Deserialize)]
-struct PostTextData {
+#[serde(rename_all = "camelCase")]
+struct PostMessageData {
     code: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_id")]
+    msg_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_id")]
+    new_msg_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_id")]
+    create_time: Option<String>,
 }
 
-#[derive(Debug, Clone)]
-struct RetryPolicy {
-    max_retries: u8,
-    initial_delay: Duration,
-    max_delay: Duration,
-    exponential_base: f64,
+/// Gewe 的消息 ID 可能以数字或字符串返回，统一保存为字符串。
+fn deserialize_id<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::Number(number)) => Some(number.to_string()),
+            Some(serde_json::Value::String(text)) if !text.is_empty() => Some(text),
+            _ => None,
+        },
+    )
 }
 
-impl Default for RetryPolicy {
-    fn default() -> Self {
-        Self {
-            max_retries: 3,
-            initial_delay: Duration::from_millis(100),
-            max_delay: Duration::from_secs(5),
-            exponential_base: 2.0,
-        }
-    }
+#[derive(Debug, Clone)]
+struct MessageIds {
+    msg_id: String,
+    new_msg_id: String,
+    create_time: String,
 }
 
+const CHECK_ONLINE_PATH: &str = "/gewe/v2/api/login/checkOnline";
+
+/// 建立 TCP/TLS 连接的超时上限，不超过请求超时的一半。
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 内存中保留的已发送消息数量上限。
+pub const SENT_HISTORY_CAPACITY: usize = 50;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ApiRet {
     Success,
@@ -122,7 +418,7 @@ impl From<i32> for ApiRet {
     }
 }
 
-impl PostTextResponse {
+impl PostMessageResponse {
     fn ret_status(&self) -> ApiRet {
         ApiRet::from(self.ret)
     }
@@ -132,77 +428,225 @@ impl PostTextResponse {
     }
 
     fn failure_code(&self) -> Option<ApiErrorCode> {
-        self.data
-            .as_ref()
-            .and_then(|data| data.code.as_deref())
-            .and_then(|code| code.parse::<i32>().ok())
-            .and_then(ApiErrorCode::from_code)
+        parse_error_code(self.data.as_ref().and_then(|data| data.code.as_deref()))
+    }
+}
+
+/// 解析 `data.code` 中携带的已知错误码。
+fn parse_error_code(code: Option<&str>) -> Option<ApiErrorCode> {
+    code.and_then(|code| code.parse::<i32>().ok())
+        .and_then(ApiErrorCode::from_code)
+}
+
+/// 将 `ret` 不为 200 的响应映射为业务错误，发送与查询接口共用，
+/// 以便两条路径得到相同的错误码与退出码。
+fn business_error(ret: i32, msg: String, code: Option<ApiErrorCode>) -> ApiBusinessError {
+    match code {
+        Some(code) => ApiBusinessError::KnownError { code },
+        None if matches!(ret, 401 | 403) => ApiBusinessError::Unauthorized,
+        None => ApiBusinessError::UnknownError {
+            code: ret,
+            message: msg,
+        },
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Mentions {
+    prefix: String,
+    ats: String,
+    wxids: Vec<String>,
+    is_at_all: bool,
+}
+
+/// 一次 Gewe API 调用的原始响应。
+struct RawResponse {
+    status: StatusCode,
+    body: String,
+    /// 实际处理请求的 Gewe 地址。
+    endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RetOnly {
+    ret: i32,
+}
+
+impl RawResponse {
+    /// Gewe 以 HTTP 401/403 或响应中的 `ret` 401/403 表示 Token 无效。
+    fn is_unauthorized(&self) -> bool {
+        matches!(
+            self.status,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+        ) || serde_json::from_str::<RetOnly>(&self.body)
+            .is_ok_and(|response| matches!(response.ret, 401 | 403))
     }
 }
 
-struct PostTextCall {
+struct PostMessageCall {
     status: StatusCode,
     body: String,
-    response: PostTextResponse,
+    endpoint: String,
+    response: PostMessageResponse,
+}
+
+impl PostMessageCall {
+    fn into_result(self) -> Result<Option<MessageIds>> {
+        if self.status.is_success() && self.response.is_success() {
+            info!("通知发送成功 (Gewe 地址: {})", self.endpoint);
+            let ids = self.response.data.and_then(|data| {
+                Some(MessageIds {
+                    msg_id: data.msg_id.unwrap_or_default(),
+                    new_msg_id: data.new_msg_id?,
+                    create_time: data.create_time.unwrap_or_default(),
+                })
+            });
+            return Ok(ids);
+        }
+
+        let code = self.response.failure_code();
+        if code.is_none() && self.response.msg.is_empty() {
+            return Err(NetworkError::HttpError {
+                status: self.status.as_u16(),
+                body: Some(self.body),
+            }
+            .into());
+        }
+        let error = business_error(
+            self.response.ret_status().code(),
+            self.response.msg.clone(),
+            code,
+        );
+
+        error!("通知发送失败: {:?} 原始响应: {}", error, self.body);
+        Err(error.into())
+    }
 }
 
 #[derive(Clone)]
 pub struct GeweApiClient {
     client: Client,
-    config: Config,
+    /// 当前生效的配置。重新加载时整体替换；每次发送开始时取一份快照，并传给该次发送的
+    /// 全部 API 调用 (查询群成员、多段文本、链接后的 @ 提醒)，因此中途重新加载不会让
+    /// 同一次发送混用两个版本的地址、Token、App ID 与 @ 列表。例外是超时与重试策略
+    /// 总是取最新配置，鉴权失败后重新读取的 Token 也会立即用于重发。
+    config: Arc<RwLock<Arc<Config>>>,
     semaphore: Arc<Semaphore>,
-    retry_policy: RetryPolicy,
-    request_timeout: Duration,
+    sent_history: Arc<Mutex<VecDeque<SentMessage>>>,
+    /// 最近一次成功处理请求的地址，后续请求优先发往该地址；`None` 表示主地址。
+    active_endpoint: Arc<Mutex<Option<String>>>,
+    contacts_cache: Arc<Mutex<Option<CachedContacts>>>,
+}
+
+/// 最近一次获取的通讯录，按 App ID 区分，有效期见 [`CONTACTS_CACHE_TTL`]。
+struct CachedContacts {
+    app_id: String,
+    fetched_at: Instant,
+    contacts: Vec<Contact>,
 }
 
 impl GeweApiClient {
+    /// 创建客户端。超时与重试策略在每次请求时从当前配置读取，热重载后立即生效；
+    /// 并发上限、连接超时、代理与 TLS 设置只在此处读取一次。
     pub fn new(config: Config) -> Result<Self> {
-        let client = Client::builder()
+        config.ensure_base_url()?;
+        let transport = &config.transport;
+        // 固定使用 rustls：客户端证书以 PEM 加载，TLS 错误也按 rustls 的类型识别
+        let mut builder = Client::builder()
+            .use_rustls_tls()
+            // 丢包而不应答的地址在连接阶段即判定为不可达，以便切换到备用地址，
+            // 而不是耗尽整个请求超时
+            .connect_timeout(CONNECT_TIMEOUT.min(config.http.request_timeout / 2))
             .pool_max_idle_per_host(5)
             .pool_idle_timeout(Duration::from_secs(30))
-            .tcp_keepalive(Duration::from_secs(60))
+            .tcp_keepalive(Duration::from_secs(60));
+        if let Some(proxy) = &transport.proxy {
+            let proxy = Proxy::all(proxy.as_str())
+                .map_err(|e| NetworkError::from_reqwest(e, config.http.request_timeout))?;
+            builder = builder.proxy(proxy);
+        }
+        for cert in &transport.ca_certs {
+            builder = builder.add_root_certificate(cert.clone());
+        }
+        if let Some(identity) = &transport.client_identity {
+            builder = builder.identity(identity.clone());
+        }
+        if transport.insecure {
+            warn!("已关闭 TLS 证书校验 (insecure)，请勿在生产环境使用");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        let client = builder
             .build()
-            .map_err(NetworkError::from)?;
+            .map_err(|e| NetworkError::from_reqwest(e, config.http.request_timeout))?;
+        let semaphore = Arc::new(Semaphore::new(config.http.max_concurrency));
 
         Ok(Self {
             client,
-            config,
-            semaphore: Arc::new(Semaphore::new(10)),
-            retry_policy: RetryPolicy::default(),
-            request_timeout: Duration::from_secs(10),
+            config: Arc::new(RwLock::new(Arc::new(config))),
+            semaphore,
+            sent_history: Arc::new(Mutex::new(VecDeque::with_capacity(SENT_HISTORY_CAPACITY))),
+            active_endpoint: Arc::new(Mutex::new(None)),
+            contacts_cache: Arc::new(Mutex::new(None)),
         })
     }
 
     pub async fn check_online(&self) -> Result<bool> {
+        let config = self.config();
         info!("正在检查微信机器人在线状态...");
 
-        let url = format!("{}/gewe/v2/api/login/checkOnline", self.config.base_url);
-        let request = CheckOnlineRequest {
-            app_id: self.config.app_id_str().to_string(),
-        };
+        let response = self
+            .send_request(
+                &config,
+                CHECK_ONLINE_PATH,
+                &Self::check_online_request(&config),
+            )
+            .await?;
+        Self::parse_online(&config, response)
+    }
 
-        let _permit = self
-            .semaphore
-            .acquire()
+    /// 已切换到备用地址时，直接向主地址发送一次 `checkOnline`，
+    /// 主地址恢复且机器人在线时切回主地址。返回是否切回。
+    pub async fn probe_primary(&self) -> bool {
+        let config = self.config();
+        let primary = config.primary_base_url();
+        match self.active_endpoint() {
+            Some(active) if active != primary => {}
+            _ => return false,
+        }
+
+        let url = format!("{}{}", primary, CHECK_ONLINE_PATH);
+        let result = self
+            .send_authorized(&config, &url, &Self::check_online_request(&config))
             .await
-            .map_err(|_| NetworkError::ConnectionRefused)?;
+            .and_then(|response| {
+                let response = RawResponse {
+                    endpoint: primary.to_string(),
+                    ..response
+                };
+                Self::parse_online(&config, response)
+            });
+        match result {
+            Ok(true) => {
+                self.mark_active(&config, primary);
+                true
+            }
+            Ok(false) => false,
+            Err(e) => {
+                info!("主地址 {} 仍不可用: {}", primary, e);
+                false
+            }
+        }
+    }
 
-        let response = timeout(
-            self.request_timeout,
-            self.client
-                .post(&url)
-                .header("X-GEWE-TOKEN", self.config.token_str())
-                .json(&request)
-                .send(),
-        )
-        .await
-        .map_err(|_| NetworkError::Timeout {
-            duration: self.request_timeout,
-        })?
-        .map_err(NetworkError::from)?;
+    fn check_online_request(config: &Config) -> CheckOnlineRequest {
+        CheckOnlineRequest {
+            app_id: config.app_id_str().to_string(),
+        }
+    }
 
-        if response.status().is_success() {
-            let data: CheckOnlineResponse = response.json().await.map_err(NetworkError::from)?;
+    fn parse_online(config: &Config, response: RawResponse) -> Result<bool> {
+        if response.status.is_success() {
+            let data: CheckOnlineResponse = serde_json::from_str(&response.body)?;
 
             if data.ret != 200 {
                 error!(
@@ -217,16 +661,17 @@ impl GeweApiClient {
             }
 
             if let Some(true) = data.data {
-                info!("机器人在线，准备就绪。");
+                info!("机器人在线，准备就绪 (Gewe 地址: {})。", response.endpoint);
                 Ok(true)
             } else {
                 error!("机器人当前不在线。");
-                error!("   - App ID: {}", self.config.app_id_str());
+                error!("   - App ID: {}", config.app_id_str());
                 Err(ApiBusinessError::BotOffline.into())
             }
         } else {
-            let status = response.status();
-            let text = response.text().await.map_err(NetworkError::from)?;
+            let RawResponse {
+                status, body: text, ..
+            } = response;
             error!("在线状态检查失败，HTTP 状态码: {}", status);
             error!("   - 响应内容: {}", text);
             Err(NetworkError::HttpError {
@@ -239,43 +684,47 @@ impl GeweApiClient {
 
     async fn get_chatroom_member_names(
         &self,
+        config: &Config,
         chatroom_id: &str,
     ) -> Result<HashMap<String, String>> {
+        let members = self.fetch_chatroom_members(config, chatroom_id).await?;
+        if members.is_empty() {
+            warn!("警告: 获取到空的群成员列表。");
+        }
+
+        Ok(members
+            .into_iter()
+            .map(|member| {
+                let name = member.name().to_string();
+                (member.wxid, name)
+            })
+            .collect())
+    }
+
+    /// 获取群成员列表，并标注群主与管理员。机器人不在群内时返回 [`ApiErrorCode::NotInGroup`]。
+    pub async fn chatroom_members(&self, chatroom_id: &str) -> Result<Vec<ChatroomMember>> {
+        self.fetch_chatroom_members(&self.config(), chatroom_id)
+            .await
+    }
+
+    async fn fetch_chatroom_members(
+        &self,
+        config: &Config,
+        chatroom_id: &str,
+    ) -> Result<Vec<ChatroomMember>> {
         info!("正在为群 {} 获取成员列表...", chatroom_id);
 
-        let url = format!(
-            "{}/gewe/v2/api/group/getChatroomMemberList",
-            self.config.base_url
-        );
         let request = GetChatroomMemberListRequest {
-            app_id: self.config.app_id_str().to_string(),
+            app_id: config.app_id_str().to_string(),
             chatroom_id: chatroom_id.to_string(),
         };
 
-        let _permit = self
-            .semaphore
-            .acquire()
-            .await
-            .map_err(|_| NetworkError::ConnectionRefused)?;
+        let response = self
+            .send_request(config, "/gewe/v2/api/group/getChatroomMemberList", &request)
+            .await?;
 
-        let response = timeout(
-            self.request_timeout,
-            self.client
-                .post(&url)
-                .header("X-GEWE-TOKEN", self.config.token_str())
-                .header(header::CONTENT_TYPE, "application/json")
-                .json(&request)
-                .send(),
-        )
-        .await
-        .map_err(|_| NetworkError::Timeout {
-            duration: self.request_timeout,
-        })?
-        .map_err(NetworkError::from)?;
-
-        if response.status().is_success() {
-            let data: GetChatroomMemberListResponse =
-                response.json().await.map_err(NetworkError::from)?;
+        if response.status.is_success() {
+            let data: GetChatroomMemberListResponse = serde_json::from_str(&response.body)?;
 
             if data.ret != 200 {
                 if data.ret == 500 && data.msg == "获取群成员列表异常:null" {
@@ -300,25 +749,15 @@ impl GeweApiClient {
                 .into());
             }
 
-            if let Some(member_data) = data.data {
-                let name_map: HashMap<String, String> = member_data
-                    .member_list
-                    .into_iter()
-                    .map(|member| {
-                        let name = member.display_name.unwrap_or(member.nick_name);
-                        (member.wxid, name)
-                    })
-                    .collect();
-
-                info!("成功获取并解析群成员列表。");
-                Ok(name_map)
-            } else {
-                warn!("警告: 获取到空的群成员列表。");
-                Ok(HashMap::new())
-            }
+            info!("成功获取并解析群成员列表。");
+            Ok(data
+                .data
+                .map(ChatroomMemberData::into_members)
+                .unwrap_or_default())
         } else {
-            let status = response.status();
-            let text = response.text().await.map_err(NetworkError::from)?;
+            let RawResponse {
+                status, body: text, ..
+            } = response;
             error!("获取群成员列表失败，状态码: {}, 响应: {}", status, text);
             Err(NetworkError::HttpError {
                 status: status.as_u16(),
@@ -328,32 +767,500 @@ impl GeweApiClient {
         }
     }
 
-    pub async fn post_text(&self, content: &str) -> Result<()> {
-        info!("准备发送通知: '{}'", content);
+    /// 机器人自己的 wxid。
+    pub(crate) async fn own_wxid(&self) -> Result<String> {
+        let config = self.config();
+        let request = GetProfileRequest {
+            app_id: config.app_id_str().to_string(),
+        };
+
+        let response = self
+            .send_request(&config, "/gewe/v2/api/personal/getProfile", &request)
+            .await?;
+        if !response.status.is_success() {
+            return Err(NetworkError::HttpError {
+                status: response.status.as_u16(),
+                body: Some(response.body),
+            }
+            .into());
+        }
+
+        let data: GetProfileResponse = serde_json::from_str(&response.body)?;
+        match data.data {
+            Some(profile) if data.ret == 200 => Ok(profile.wxid),
+            _ => Err(ApiBusinessError::UnknownError {
+                code: data.ret,
+                message: data.msg,
+            }
+            .into()),
+        }
+    }
+
+    /// 获取机器人账号的通讯录 (好友与已保存的群聊)，并补全昵称与备注。
+    /// 结果按 App ID 缓存 [`CONTACTS_CACHE_TTL`]，避免频繁调用时反复分批查询。
+    pub async fn list_contacts(&self) -> Result<Vec<Contact>> {
+        let config = self.config();
+        if let Some(cached) = self
+            .contacts_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .filter(|cached| {
+                cached.app_id == config.app_id_str()
+                    && cached.fetched_at.elapsed() < CONTACTS_CACHE_TTL
+            })
+        {
+            return Ok(cached.contacts.clone());
+        }
+
+        info!("正在获取通讯录列表...");
+        let list: ContactsListData = self
+            .fetch_data(
+                &config,
+                "/gewe/v2/api/contacts/fetchContactsList",
+                &FetchContactsListRequest {
+                    app_id: config.app_id_str().to_string(),
+                },
+            )
+            .await?
+            .unwrap_or_default();
+
+        let wxids: Vec<String> = list
+            .friends
+            .iter()
+            .chain(&list.chatrooms)
+            .cloned()
+            .collect();
+        let mut briefs = HashMap::new();
+        for batch in wxids.chunks(BRIEF_INFO_BATCH) {
+            let infos: Vec<BriefInfo> = self
+                .fetch_data(
+                    &config,
+                    "/gewe/v2/api/contacts/getBriefInfo",
+                    &GetBriefInfoRequest {
+                        app_id: config.app_id_str().to_string(),
+                        wxids: batch.to_vec(),
+                    },
+                )
+                .await?
+                .unwrap_or_default();
+            briefs.extend(infos.into_iter().map(|info| (info.user_name.clone(), info)));
+        }
+
+        let non_empty = |value: Option<String>| value.filter(|value| !value.trim().is_empty());
+        let kinds = list
+            .friends
+            .into_iter()
+            .map(|wxid| (wxid, ContactKind::Friend))
+            .chain(
+                list.chatrooms
+                    .into_iter()
+                    .map(|wxid| (wxid, ContactKind::Chatroom)),
+            );
+        let contacts: Vec<Contact> = kinds
+            .map(|(wxid, kind)| {
+                let (nickname, remark) = match briefs.remove(&wxid) {
+                    Some(brief) => (non_empty(brief.nick_name), non_empty(brief.remark)),
+                    None => (None, None),
+                };
+                Contact {
+                    wxid,
+                    kind,
+                    nickname,
+                    remark,
+                }
+            })
+            .collect();
+        info!("成功获取通讯录，共 {} 个联系人。", contacts.len());
+        *self
+            .contacts_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(CachedContacts {
+            app_id: config.app_id_str().to_string(),
+            fetched_at: Instant::now(),
+            contacts: contacts.clone(),
+        });
+        Ok(contacts)
+    }
+
+    /// 发送请求并解析 `{ret, msg, data}` 响应，`ret` 不为 200 时返回业务错误。
+    async fn fetch_data<Req: Serialize, T: serde::de::DeserializeOwned>(
+        &self,
+        config: &Config,
+        path: &str,
+        request: &Req,
+    ) -> Result<Option<T>> {
+        let response = self.send_request(config, path, request).await?;
+        if !response.status.is_success() {
+            return Err(NetworkError::HttpError {
+                status: response.status.as_u16(),
+                body: Some(response.body),
+            }
+            .into());
+        }
+
+        let data: ApiResponse<serde_json::Value> = serde_json::from_str(&response.body)?;
+        if data.ret != 200 {
+            error!("请求 {} 失败 (ret: {}, msg: {})", path, data.ret, data.msg);
+            let code = parse_error_code(
+                data.data
+                    .as_ref()
+                    .and_then(|data| data.get("code"))
+                    .and_then(|code| code.as_str()),
+            );
+            let error = business_error(data.ret, data.msg, code);
+            // Gewe 没有表示机器人离线的专用错误码，未知错误时检查一次在线状态，
+            // 使离线仍以 bot_offline 报告
+            if let ApiBusinessError::UnknownError { .. } = error {
+                if let Err(offline @ GeweNoticeError::Business(ApiBusinessError::BotOffline)) = self
+                    .send_request(
+                        config,
+                        CHECK_ONLINE_PATH,
+                        &Self::check_online_request(config),
+                    )
+                    .await
+                    .and_then(|response| Self::parse_online(config, response))
+                {
+                    return Err(offline);
+                }
+            }
+            return Err(error.into());
+        }
+        match data.data {
+            Some(serde_json::Value::Null) | None => Ok(None),
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+        }
+    }
+
+    /// 当前生效配置的快照。
+    pub fn config(&self) -> Arc<Config> {
+        self.config
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// 原子地替换生效配置，之后开始的请求都会使用新配置。
+    pub fn replace_config(&self, config: Config) -> Result<()> {
+        config.ensure_base_url()?;
+        *self
+            .config
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(config);
+        Ok(())
+    }
+
+    pub async fn post_text(&self, content: &str) -> Result<Delivery> {
+        let config = self.config();
+        self.post_text_to(&config.default_recipient(), content)
+            .await
+    }
+
+    /// 发送文本通知。连同 @ 前缀超过 `max_message_length` 的内容会被拆分为多段依次发送，
+    /// @ 前缀只出现在第一段；中途失败时返回 [`GeweNoticeError::PartialDelivery`]。
+    pub async fn post_text_to(&self, recipient: &Recipient, content: &str) -> Result<Delivery> {
+        let config = self.config();
+        info!("准备发送通知至 {}: '{}'", recipient.wxid, content);
+        let started = Instant::now();
+        let mut delivery = Delivery::new(recipient);
+
+        // 先确定 @ 前缀，拆分时为第一段预留前缀的长度
+        let mentions = self
+            .send_with_retry(|| self.resolve_mentions(&config, recipient))
+            .await?;
+        let prefix_len = mentions
+            .as_ref()
+            .map_or(0, |mentions| mentions.prefix.chars().count());
+        let parts = split_message(content, config.max_message_length, prefix_len);
+        let total = parts.len();
+        if total > 1 {
+            info!(
+                "通知内容超过 {} 字符，将分 {} 段发送",
+                config.max_message_length, total
+            );
+        }
+
+        delivery.parts = total;
+        for (index, part) in parts.iter().enumerate() {
+            let mentions = if index == 0 { mentions.as_ref() } else { None };
+            let result = self
+                .send_counted(&mut delivery.attempts, || {
+                    self.send_text(&config, recipient, part, mentions)
+                })
+                .await;
+
+            match result {
+                Ok(sent) => delivery.record(sent),
+                Err(err) if total == 1 => return Err(err),
+                Err(err) => {
+                    error!("第 {}/{} 段发送失败: {}", index + 1, total, err);
+                    return Err(GeweNoticeError::PartialDelivery {
+                        part: index + 1,
+                        total,
+                        source: Box::new(err),
+                    });
+                }
+            }
+        }
+
+        Ok(delivery.finish(started))
+    }
+
+    pub async fn post_image(&self, image_url: &str) -> Result<Delivery> {
+        let config = self.config();
+        info!("准备发送图片: '{}'", image_url);
+        let started = Instant::now();
+        let recipient = config.default_recipient();
+        let mut delivery = Delivery::new(&recipient);
+        let sent = self
+            .send_counted(&mut delivery.attempts, || {
+                self.post_image_internal(&config, &recipient, image_url)
+            })
+            .await?;
+        delivery.record(sent);
+        Ok(delivery.finish(started))
+    }
 
-        let operation = timeout(Duration::from_secs(30), self.post_text_with_retry(content));
+    pub async fn post_file(&self, file_name: &str, file_url: &str) -> Result<Delivery> {
+        let config = self.config();
+        info!("准备发送文件: '{}' ({})", file_name, file_url);
+        validate_file_name(file_name)?;
+
+        if let Some(size) = self.probe_file_size(file_url).await {
+            if size > MAX_FILE_SIZE {
+                error!("文件 {} 过大: {} 字节", file_name, size);
+                return Err(AttachmentError::TooLarge {
+                    size,
+                    limit: MAX_FILE_SIZE,
+                }
+                .into());
+            }
+        }
+
+        let started = Instant::now();
+        let recipient = config.default_recipient();
+        let mut delivery = Delivery::new(&recipient);
+        let sent = self
+            .send_counted(&mut delivery.attempts, || {
+                self.post_file_internal(&config, &recipient, file_name, file_url)
+            })
+            .await?;
+        delivery.record(sent);
+        Ok(delivery.finish(started))
+    }
+
+    /// 发送链接卡片。链接消息本身不支持 @，因此在群聊中配置了 @ 列表时，
+    /// 会在卡片之后追加一条带相同 @ 前缀的文本提醒。
+    pub async fn post_link(&self, link: &LinkMessage) -> Result<Delivery> {
+        let config = self.config();
+        self.post_link_to(&config.default_recipient(), link).await
+    }
+
+    /// 向指定接收者发送链接卡片；`recipient` 没有 @ 列表时不追加提醒。
+    pub async fn post_link_to(
+        &self,
+        recipient: &Recipient,
+        link: &LinkMessage,
+    ) -> Result<Delivery> {
+        let config = self.config();
+        info!("准备发送链接: '{}' ({})", link.title, link.link_url);
+
+        let started = Instant::now();
+        let recipient = recipient.clone();
+        let mut delivery = Delivery::new(&recipient);
+        let mentions = self
+            .send_with_retry(|| self.resolve_mentions(&config, &recipient))
+            .await?;
+        let sent = self
+            .send_counted(&mut delivery.attempts, || {
+                self.post_link_internal(&config, &recipient, link)
+            })
+            .await?;
+        delivery.record(sent);
+
+        if let Some(mentions) = mentions {
+            let reminder = format!("请查看: {}", link.title);
+            let sent = self
+                .send_counted(&mut delivery.attempts, || {
+                    self.send_text(&config, &recipient, &reminder, Some(&mentions))
+                })
+                .await;
+            match sent {
+                Ok(sent) => delivery.record(sent),
+                // 卡片已经送达，提醒失败不作为错误返回，否则调用方重试会重复发送卡片
+                Err(e) => {
+                    warn!("链接卡片已发送，但 @ 提醒发送失败: {}", e);
+                    delivery.warning = Some(format!("@ 提醒发送失败 [{}]: {}", e.code(), e));
+                }
+            }
+        }
+
+        Ok(delivery.finish(started))
+    }
+
+    /// 最近发送且尚未撤回的消息，按发送顺序排列。
+    pub fn sent_messages(&self) -> Vec<SentMessage> {
+        self.history().iter().cloned().collect()
+    }
+
+    /// 撤回一条之前发送的消息。`new_msg_id` 为空时撤回最近发送的一条。
+    ///
+    /// 只能撤回本进程发送、且仍保留在内存历史中的消息；微信本身只允许撤回
+    /// 两分钟内发送的消息，超时由 Gewe 返回错误。
+    pub async fn revoke_message(&self, new_msg_id: Option<&str>) -> Result<SentMessage> {
+        let message = {
+            let history = self.history();
+            match new_msg_id {
+                Some(id) => history.iter().rev().find(|m| m.new_msg_id == id).cloned(),
+                None => history.back().cloned(),
+            }
+        }
+        .ok_or_else(|| {
+            GeweNoticeError::UnknownMessage(new_msg_id.unwrap_or("最近一条").to_string())
+        })?;
+
+        info!(
+            "准备撤回消息 {} (接收者 {})",
+            message.new_msg_id, message.to_wxid
+        );
+        let config = self.config();
+        self.send_with_retry(|| self.revoke_internal(&config, &message))
+            .await?;
+
+        self.history()
+            .retain(|sent| sent.new_msg_id != message.new_msg_id);
+        Ok(message)
+    }
+
+    fn history(&self) -> std::sync::MutexGuard<'_, VecDeque<SentMessage>> {
+        self.sent_history
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn sent_part(
+        &self,
+        recipient: &Recipient,
+        kind: MessageKind,
+        content: String,
+        ids: Option<MessageIds>,
+        endpoint: String,
+    ) -> SentPart {
+        SentPart {
+            message: self.remember(recipient, kind, &content, ids),
+            content,
+            mentioned: Vec::new(),
+            at_all_fallback: false,
+            endpoint,
+        }
+    }
+
+    fn remember(
+        &self,
+        recipient: &Recipient,
+        kind: MessageKind,
+        preview: &str,
+        ids: Option<MessageIds>,
+    ) -> Option<SentMessage> {
+        let ids = ids?;
+        let message = SentMessage {
+            kind,
+            to_wxid: recipient.wxid.as_str().to_string(),
+            preview: preview.chars().take(50).collect(),
+            msg_id: ids.msg_id,
+            new_msg_id: ids.new_msg_id,
+            create_time: ids.create_time,
+        };
+
+        let mut history = self.history();
+        if history.len() == SENT_HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(message.clone());
+        Some(message)
+    }
+
+    async fn probe_file_size(&self, file_url: &str) -> Option<u64> {
+        let _permit = self.semaphore.acquire().await.ok()?;
+        let request_timeout = self.config().http.request_timeout;
+
+        let response = match timeout(request_timeout, self.client.head(file_url).send()).await {
+            Ok(Ok(response)) if response.status().is_success() => response,
+            Ok(Ok(response)) => {
+                warn!(
+                    "警告: 无法获取文件大小 (HTTP {})，跳过大小检查。",
+                    response.status()
+                );
+                return None;
+            }
+            Ok(Err(err)) => {
+                warn!("警告: 无法获取文件大小 ({})，跳过大小检查。", err);
+                return None;
+            }
+            Err(_) => {
+                warn!("警告: 获取文件大小超时，跳过大小检查。");
+                return None;
+            }
+        };
+
+        response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+    }
+
+    /// 与 [`Self::send_with_retry`] 相同，并把实际尝试次数累加到 `attempts`。
+    async fn send_counted<F, Fut, T>(&self, attempts: &mut u32, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut count = 0;
+        let result = self
+            .send_with_retry(|| {
+                count += 1;
+                operation()
+            })
+            .await;
+        *attempts += count;
+        result
+    }
+
+    async fn send_with_retry<F, Fut, T>(&self, operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let send_timeout = self.config().http.send_timeout;
+        let operation = timeout(send_timeout, self.retry(operation));
 
         operation.await.map_err(|_| NetworkError::Timeout {
-            duration: Duration::from_secs(30),
+            duration: send_timeout,
         })?
     }
 
-    async fn post_text_with_retry(&self, content: &str) -> Result<()> {
+    async fn retry<F, Fut, T>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let policy = self.config().http.retry.clone();
         let mut attempts = 0;
         let mut last_error = None;
 
-        while attempts < self.retry_policy.max_retries {
-            match self.post_text_internal(content).await {
-                Ok(()) => return Ok(()),
+        while attempts < policy.max_attempts {
+            match operation().await {
+                Ok(value) => return Ok(value),
                 Err(e) if e.is_retryable() => {
                     last_error = Some(e.clone());
                     attempts += 1;
 
-                    if attempts < self.retry_policy.max_retries {
-                        let delay = self.calculate_backoff(attempts);
+                    if attempts < policy.max_attempts {
+                        let delay = Self::calculate_backoff(&policy, attempts);
                         warn!(
                             "重试 {}/{}: 等待 {:?}",
-                            attempts, self.retry_policy.max_retries, delay
+                            attempts, policy.max_attempts, delay
                         );
                         sleep(delay).await;
                     }
@@ -371,123 +1278,436 @@ impl GeweApiClient {
         }))
     }
 
-    fn calculate_backoff(&self, attempt: u8) -> Duration {
-        let exponential = self.retry_policy.initial_delay.as_millis() as f64
-            * self.retry_policy.exponential_base.powi(attempt as i32 - 1);
+    fn calculate_backoff(policy: &RetryPolicy, attempt: u32) -> Duration {
+        let exponential =
+            policy.initial_delay.as_millis() as f64 * policy.multiplier.powi(attempt as i32 - 1);
 
         let jittered = exponential * (0.5 + rand::random::<f64>() * 0.5);
 
-        Duration::from_millis(jittered.min(self.retry_policy.max_delay.as_millis() as f64) as u64)
+        Duration::from_millis(jittered.min(policy.max_delay.as_millis() as f64) as u64)
     }
 
-    async fn post_text_internal(&self, content: &str) -> Result<()> {
-        let mut final_content = content.to_string();
-        let mut ats_payload = None;
-        let normalized_at_list = self.config.normalized_at_list();
-        let is_at_all = matches!(normalized_at_list.as_ref(), Some(list) if list.len() == 1 && list[0] == "all");
+    async fn resolve_mentions(
+        &self,
+        config: &Config,
+        recipient: &Recipient,
+    ) -> Result<Option<Mentions>> {
+        if !recipient.is_chatroom() {
+            return Ok(None);
+        }
+        let Some(at_list) = recipient.normalized_at_list() else {
+            return Ok(None);
+        };
 
-        if self.config.is_chatroom() {
-            if let Some(ref at_list) = normalized_at_list {
-                info!("检测到群聊 @ 请求，正在处理...");
+        info!("检测到群聊 @ 请求，正在处理...");
 
-                if is_at_all {
-                    ats_payload = Some("notify@all".to_string());
-                    final_content = format!("@所有人 {}", content);
-                    info!("已将 @ 全体成员，并在内容中添加 @ 所有人。");
-                } else {
-                    match self.get_chatroom_member_names(self.config.wxid_str()).await {
-                        Ok(member_map) => {
-                            let mut at_names = Vec::new();
-                            let mut valid_wxids = Vec::new();
-
-                            for wxid in at_list {
-                                if let Some(name) = member_map.get(wxid) {
-                                    at_names.push(format!("@{}", name));
-                                    valid_wxids.push(wxid.clone());
-                                } else {
-                                    warn!("警告: 在群成员列表中未找到 wxid: {}", wxid);
-                                }
-                            }
-
-                            if !at_names.is_empty() {
-                                let mention_string = format!("{} ", at_names.join(" "));
-                                final_content = format!("{}{}", mention_string, content);
-                                ats_payload = Some(valid_wxids.join(","));
-                                info!("最终 @ 内容: {}", mention_string.trim_end());
-                                info!("最终 ats 参数: {:?}", ats_payload);
-                            }
-                        }
-                        Err(err) => {
-                            error!("获取群成员列表失败，无法执行 @ 操作: {}", err);
-                            return Err(err);
-                        }
-                    }
-                }
+        if recipient.mentions_all() {
+            info!("已将 @ 全体成员，并在内容中添加 @ 所有人。");
+            return Ok(Some(Mentions {
+                prefix: "@所有人 ".to_string(),
+                ats: "notify@all".to_string(),
+                wxids: vec!["all".to_string()],
+                is_at_all: true,
+            }));
+        }
+
+        let member_map = match self
+            .get_chatroom_member_names(config, recipient.wxid.as_str())
+            .await
+        {
+            Ok(member_map) => member_map,
+            Err(err) => {
+                error!("获取群成员列表失败，无法执行 @ 操作: {}", err);
+                return Err(err);
             }
+        };
+
+        let mut at_names = Vec::new();
+        let mut valid_wxids = Vec::new();
+
+        for wxid in &at_list {
+            if let Some(name) = member_map.get(wxid) {
+                at_names.push(format!("@{}", name));
+                valid_wxids.push(wxid.clone());
+            } else {
+                warn!("警告: 在群成员列表中未找到 wxid: {}", wxid);
+            }
+        }
+
+        if at_names.is_empty() {
+            return Ok(None);
         }
 
-        let url = format!("{}/gewe/v2/api/message/postText", self.config.base_url);
+        let mentions = Mentions {
+            prefix: format!("{} ", at_names.join(" ")),
+            ats: valid_wxids.join(","),
+            wxids: valid_wxids,
+            is_at_all: false,
+        };
+        info!("最终 @ 内容: {}", mentions.prefix.trim_end());
+        info!("最终 ats 参数: {:?}", mentions.ats);
+        Ok(Some(mentions))
+    }
+
+    async fn send_text(
+        &self,
+        config: &Config,
+        recipient: &Recipient,
+        content: &str,
+        mentions: Option<&Mentions>,
+    ) -> Result<SentPart> {
+        let path = "/gewe/v2/api/message/postText";
         let request = PostTextRequest {
-            app_id: self.config.app_id_str().to_string(),
-            to_wxid: self.config.wxid_str().to_string(),
-            content: final_content,
-            ats: ats_payload,
+            app_id: config.app_id_str().to_string(),
+            to_wxid: recipient.wxid.as_str().to_string(),
+            content: match mentions {
+                Some(mentions) => format!("{}{}", mentions.prefix, content),
+                None => content.to_string(),
+            },
+            ats: mentions.map(|mentions| mentions.ats.clone()),
         };
 
-        let mut call = self.execute_post_text(&url, &request).await?;
-        let mut ret_status = call.response.ret_status();
-        let mut failure_code = call.response.failure_code();
+        let mut call = self.execute_post_message(config, path, &request).await?;
+        let ret_status = call.response.ret_status();
+        let failure_code = call.response.failure_code();
 
-        let should_retry_at_all = self.config.is_chatroom()
-            && is_at_all
+        let should_retry_at_all = mentions.is_some_and(|mentions| mentions.is_at_all)
             && matches!(
                 (ret_status, failure_code.as_ref()),
                 (ApiRet::Failure(500), Some(ApiErrorCode::PermissionDenied))
             );
 
+        let mut final_request = request;
         if should_retry_at_all {
             warn!("警告: @ 全体成员失败，无权限，将尝试不 @ 全体成员重试。");
 
-            let mut retry_request = request.clone();
-            retry_request.content = content.to_string();
-            retry_request.ats = None;
+            final_request.content = content.to_string();
+            final_request.ats = None;
 
-            call = self.execute_post_text(&url, &retry_request).await?;
-            ret_status = call.response.ret_status();
-            failure_code = call.response.failure_code();
+            call = self
+                .execute_post_message(config, path, &final_request)
+                .await?;
         }
 
-        if call.status.is_success() && call.response.is_success() {
-            info!("通知发送成功");
-            return Ok(());
+        let endpoint = call.endpoint.clone();
+        let ids = call.into_result()?;
+        let mut part = self.sent_part(
+            recipient,
+            MessageKind::Text,
+            final_request.content,
+            ids,
+            endpoint,
+        );
+        part.at_all_fallback = should_retry_at_all;
+        if final_request.ats.is_some() {
+            part.mentioned = mentions.map(|m| m.wxids.clone()).unwrap_or_default();
         }
+        Ok(part)
+    }
 
-        let error = match failure_code {
-            Some(code) => ApiBusinessError::KnownError { code },
-            None if !call.response.msg.is_empty() => ApiBusinessError::UnknownError {
-                code: ret_status.code(),
-                message: call.response.msg.clone(),
-            },
-            None => {
-                return Err(NetworkError::HttpError {
-                    status: call.status.as_u16(),
-                    body: Some(call.body.clone()),
-                }
-                .into())
-            }
+    async fn post_image_internal(
+        &self,
+        config: &Config,
+        recipient: &Recipient,
+        image_url: &str,
+    ) -> Result<SentPart> {
+        let path = "/gewe/v2/api/message/postImage";
+        let request = PostImageRequest {
+            app_id: config.app_id_str().to_string(),
+            to_wxid: recipient.wxid.as_str().to_string(),
+            img_url: image_url.to_string(),
         };
 
-        error!("通知发送失败: {:?} 原始响应: {}", error, call.body);
-        Err(error.into())
+        let call = self.execute_post_message(config, path, &request).await?;
+        let endpoint = call.endpoint.clone();
+        let ids = call.into_result()?;
+        Ok(self.sent_part(
+            recipient,
+            MessageKind::Image,
+            image_url.to_string(),
+            ids,
+            endpoint,
+        ))
+    }
+
+    async fn post_link_internal(
+        &self,
+        config: &Config,
+        recipient: &Recipient,
+        link: &LinkMessage,
+    ) -> Result<SentPart> {
+        let path = "/gewe/v2/api/message/postLink";
+        let request = PostLinkRequest {
+            app_id: config.app_id_str().to_string(),
+            to_wxid: recipient.wxid.as_str().to_string(),
+            title: link.title.clone(),
+            desc: link.desc.clone(),
+            link_url: link.link_url.clone(),
+            thumb_url: link.thumb_url.clone(),
+        };
+
+        let call = self.execute_post_message(config, path, &request).await?;
+        let endpoint = call.endpoint.clone();
+        let ids = call.into_result()?;
+        Ok(self.sent_part(
+            recipient,
+            MessageKind::Link,
+            link.title.clone(),
+            ids,
+            endpoint,
+        ))
+    }
+
+    async fn post_file_internal(
+        &self,
+        config: &Config,
+        recipient: &Recipient,
+        file_name: &str,
+        file_url: &str,
+    ) -> Result<SentPart> {
+        let path = "/gewe/v2/api/message/postFile";
+        let request = PostFileRequest {
+            app_id: config.app_id_str().to_string(),
+            to_wxid: recipient.wxid.as_str().to_string(),
+            file_url: file_url.to_string(),
+            file_name: file_name.to_string(),
+        };
+
+        let call = self.execute_post_message(config, path, &request).await?;
+        let endpoint = call.endpoint.clone();
+        let ids = call.into_result()?;
+        Ok(self.sent_part(
+            recipient,
+            MessageKind::File,
+            file_name.to_string(),
+            ids,
+            endpoint,
+        ))
+    }
+
+    async fn revoke_internal(&self, config: &Config, message: &SentMessage) -> Result<()> {
+        let path = "/gewe/v2/api/message/revokeMsg";
+        let request = RevokeMsgRequest {
+            app_id: config.app_id_str().to_string(),
+            to_wxid: message.to_wxid.clone(),
+            msg_id: message.msg_id.clone(),
+            new_msg_id: message.new_msg_id.clone(),
+            create_time: message.create_time.clone(),
+        };
+
+        self.execute_post_message(config, path, &request)
+            .await?
+            .into_result()
+            .map(|_| ())
+    }
+}
+
+impl Delivery {
+    fn new(recipient: &Recipient) -> Self {
+        Self {
+            recipient: recipient.wxid.as_str().to_string(),
+            parts: 1,
+            messages: Vec::new(),
+            contents: Vec::new(),
+            mentioned: Vec::new(),
+            at_all_fallback: false,
+            attempts: 0,
+            elapsed_ms: 0,
+            endpoint: String::new(),
+            warning: None,
+        }
+    }
+
+    fn record(&mut self, part: SentPart) {
+        self.messages.extend(part.message);
+        self.contents.push(part.content);
+        self.mentioned.extend(part.mentioned);
+        self.at_all_fallback |= part.at_all_fallback;
+        self.endpoint = part.endpoint;
+    }
+
+    fn finish(mut self, started: Instant) -> Self {
+        self.elapsed_ms = started.elapsed().as_millis() as u64;
+        self
+    }
+}
+
+/// 请求未到达 Gewe 的错误，可以安全地换一个地址重发。
+fn is_unreachable(err: &GeweNoticeError) -> bool {
+    matches!(
+        err,
+        GeweNoticeError::Network(
+            NetworkError::ConnectionRefused
+                | NetworkError::ConnectTimeout
+                | NetworkError::DnsResolution { .. }
+        )
+    )
+}
+
+pub(crate) fn validate_file_name(file_name: &str) -> Result<()> {
+    let extension = match file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() => ext.to_ascii_lowercase(),
+        _ => {
+            return Err(AttachmentError::MissingExtension {
+                file_name: file_name.to_string(),
+            }
+            .into())
+        }
+    };
+
+    if !ALLOWED_FILE_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(AttachmentError::UnsupportedExtension {
+            file_name: file_name.to_string(),
+            extension,
+        }
+        .into());
     }
+
+    Ok(())
 }
 
 impl GeweApiClient {
-    async fn execute_post_text(
+    async fn execute_post_message<T: Serialize>(
         &self,
+        config: &Config,
+        path: &str,
+        request: &T,
+    ) -> Result<PostMessageCall> {
+        let RawResponse {
+            status,
+            body,
+            endpoint,
+        } = self.send_request(config, path, request).await?;
+        let parsed = serde_json::from_str::<PostMessageResponse>(&body)?;
+
+        Ok(PostMessageCall {
+            status,
+            body,
+            endpoint,
+            response: parsed,
+        })
+    }
+
+    /// 依次尝试 `config.base_urls` 发送请求，从最近一次成功的地址开始。
+    /// 连接失败或返回 5xx 时切换到下一个地址；请求超时不切换，以免重复发送。
+    async fn send_request<T: Serialize>(
+        &self,
+        config: &Config,
+        path: &str,
+        request: &T,
+    ) -> Result<RawResponse> {
+        let endpoints = self.endpoint_order(config);
+        let mut endpoints = endpoints.iter().peekable();
+        let mut result = Err(ConfigValidationError::Missing {
+            key: "base_url",
+            env: "GEWE_NOTICE_BASE_URL",
+        }
+        .into());
+
+        while let Some(endpoint) = endpoints.next() {
+            let url = format!("{}{}", endpoint, path);
+            result = self
+                .send_authorized(config, &url, request)
+                .await
+                .map(|response| RawResponse {
+                    endpoint: endpoint.clone(),
+                    ..response
+                });
+            let reason = match &result {
+                Err(e) if is_unreachable(e) => e.to_string(),
+                Ok(response) if response.status.is_server_error() => {
+                    format!("HTTP {}", response.status)
+                }
+                _ => {
+                    self.mark_active(config, endpoint);
+                    return result;
+                }
+            };
+            if let Some(next) = endpoints.peek() {
+                warn!(
+                    "Gewe 地址 {} 不可用 ({})，切换到 {}",
+                    endpoint, reason, next
+                );
+            }
+        }
+        result
+    }
+
+    /// 按尝试顺序排列的地址：最近一次成功的地址在前，其余保持配置顺序。
+    fn endpoint_order(&self, config: &Config) -> Vec<String> {
+        let mut endpoints = config.base_urls.clone();
+        if let Some(active) = self.active_endpoint() {
+            if let Some(position) = endpoints.iter().position(|url| *url == active) {
+                let active = endpoints.remove(position);
+                endpoints.insert(0, active);
+            }
+        }
+        endpoints
+    }
+
+    /// 当前优先使用的 Gewe 地址；`None` 表示主地址。
+    pub fn active_endpoint(&self) -> Option<String> {
+        self.active_endpoint
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn mark_active(&self, config: &Config, endpoint: &str) {
+        let mut active = self
+            .active_endpoint
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let primary = config.primary_base_url();
+        if active.as_deref().unwrap_or(primary) == endpoint {
+            return;
+        }
+
+        if endpoint == primary {
+            info!("已切回主地址 {}", endpoint);
+            *active = None;
+        } else {
+            warn!("Gewe 地址已切换为 {} (主地址 {} 不可用)", endpoint, primary);
+            *active = Some(endpoint.to_string());
+        }
+    }
+
+    /// 携带 `config` 中的 Token 发送请求。若 Gewe 判定 Token 无效且 Token 来自文件或命令，
+    /// 重新读取 Token 后再发送一次。
+    async fn send_authorized<T: Serialize>(
+        &self,
+        config: &Config,
         url: &str,
-        request: &PostTextRequest,
-    ) -> Result<PostTextCall> {
+        request: &T,
+    ) -> Result<RawResponse> {
+        let token = config.token_str();
+        let request_timeout = config.http.request_timeout;
+        let response = self
+            .send_once(url, request, &token, request_timeout)
+            .await?;
+        if !response.is_unauthorized() {
+            return Ok(response);
+        }
+
+        if !self.refresh_token(&token).await {
+            return Err(ApiBusinessError::Unauthorized.into());
+        }
+        let response = self
+            .send_once(url, request, &self.config().token_str(), request_timeout)
+            .await?;
+        if response.is_unauthorized() {
+            return Err(ApiBusinessError::Unauthorized.into());
+        }
+        Ok(response)
+    }
+
+    async fn send_once<T: Serialize>(
+        &self,
+        url: &str,
+        request: &T,
+        token: &str,
+        request_timeout: Duration,
+    ) -> Result<RawResponse> {
         let _permit = self
             .semaphore
             .acquire()
@@ -495,28 +1715,79 @@ impl GeweApiClient {
             .map_err(|_| NetworkError::ConnectionRefused)?;
 
         let response = timeout(
-            self.request_timeout,
+            request_timeout,
             self.client
                 .post(url)
-                .header("X-GEWE-TOKEN", self.config.token_str())
+                .header("X-GEWE-TOKEN", token)
                 .header(header::CONTENT_TYPE, "application/json")
                 .json(request)
                 .send(),
         )
         .await
         .map_err(|_| NetworkError::Timeout {
-            duration: self.request_timeout,
+            duration: request_timeout,
         })?
-        .map_err(NetworkError::from)?;
+        .map_err(|e| NetworkError::from_reqwest(e, request_timeout))?;
 
         let status = response.status();
-        let body = response.text().await.map_err(NetworkError::from)?;
-        let parsed = serde_json::from_str::<PostTextResponse>(&body)?;
-
-        Ok(PostTextCall {
+        let body = response
+            .text()
+            .await
+            .map_err(|e| NetworkError::from_reqwest(e, request_timeout))?;
+        Ok(RawResponse {
             status,
             body,
-            response: parsed,
+            endpoint: String::new(),
         })
     }
+
+    /// 从文件或命令重新读取 Token。读到与 `stale` 不同的有效 Token 时替换生效配置并返回 `true`。
+    async fn refresh_token(&self, stale: &str) -> bool {
+        let config = self.config();
+        if config.token_str() != stale {
+            // 其他并发请求或热重载已经换过 Token
+            return true;
+        }
+        if !config.token_source.is_refreshable() {
+            error!("Gewe 返回鉴权失败，请检查 Token 是否有效。");
+            return false;
+        }
+
+        warn!(
+            "Gewe 返回鉴权失败，正在从{}重新读取 Token...",
+            config.token_source
+        );
+        let source = config.token_source.clone();
+        let token = match tokio::task::spawn_blocking(move || source.read()).await {
+            Ok(Ok(Some(token))) => token,
+            Ok(Ok(None)) => return false,
+            Ok(Err(e)) => {
+                error!("重新读取 Token 失败: {}", e);
+                return false;
+            }
+            Err(e) => {
+                error!("重新读取 Token 的任务异常: {}", e);
+                return false;
+            }
+        };
+        if token.as_str() == stale {
+            error!("重新读取的 Token 没有变化，请检查 Token 是否有效。");
+            return false;
+        }
+
+        {
+            // 只在持锁期间替换 Token，避免覆盖读取命令运行期间发生的热重载
+            let mut current = self
+                .config
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if current.token_str() == stale {
+                let mut updated = (**current).clone();
+                updated.token = token;
+                *current = Arc::new(updated);
+            }
+        }
+        info!("已从{}重新读取 Token。", config.token_source);
+        true
+    }
 }
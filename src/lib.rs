@@ -1,4 +1,17 @@
+pub mod cli;
 pub mod config;
+pub mod config_file;
+pub mod contacts;
+pub mod doctor;
 pub mod errors;
+pub mod exec;
+pub mod failover;
 pub mod gewe_api;
+pub mod members;
+pub mod quiet_hours;
+pub mod reload;
+pub mod render;
+pub mod routing;
+pub mod send;
 pub mod server;
+pub mod watch;
@@ -1,3 +1,4 @@
+use clap::Parser;
 use rmcp::{
     service::{serve_server, QuitReason},
     transport::stdio,
@@ -5,27 +6,140 @@ use rmcp::{
 use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
-use gewe_notice_mcp::config::Config;
+use gewe_notice_mcp::cli::{Cli, Command};
+use gewe_notice_mcp::config::{Config, RawConfig};
+use gewe_notice_mcp::contacts::{self, ContactFilter};
+use gewe_notice_mcp::doctor;
+use gewe_notice_mcp::exec;
+use gewe_notice_mcp::failover;
 use gewe_notice_mcp::gewe_api::GeweApiClient;
+use gewe_notice_mcp::members;
+use gewe_notice_mcp::quiet_hours;
+use gewe_notice_mcp::reload;
+use gewe_notice_mcp::send;
 use gewe_notice_mcp::server::GeweNoticeServer;
+use gewe_notice_mcp::watch::{self, WatchOptions};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    // 子命令的结果输出到 stdout，默认只在 stderr 显示警告与错误
+    let default_level = if cli.command.is_some() {
+        "warn"
+    } else {
+        "info"
+    };
     tracing_subscriber::fmt()
         .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level)),
         )
         .with_writer(std::io::stderr)
         .with_target(false)
         .init();
 
+    match cli.command {
+        None => serve(cli.config).await,
+        Some(Command::Doctor) => {
+            let report = doctor::run(cli.config).await;
+            print!("{}", report);
+            std::process::exit(if report.has_failures() { 1 } else { 0 });
+        }
+        Some(Command::Exec { command }) => {
+            std::process::exit(exec::run(cli.config, &command).await);
+        }
+        Some(Command::Contacts { query, kind, json }) => {
+            let filter = ContactFilter { kind, query };
+            match contacts::run(cli.config, &filter).await {
+                Ok(list) if json => {
+                    println!("{}", serde_json::to_string_pretty(&list)?);
+                    Ok(())
+                }
+                Ok(list) if list.is_empty() => {
+                    eprintln!("没有匹配的联系人");
+                    Ok(())
+                }
+                Ok(list) => {
+                    print!("{}", contacts::render_table(&list));
+                    Ok(())
+                }
+                Err(e) => {
+                    eprintln!("获取通讯录失败 [{}]: {}", e.code(), e);
+                    std::process::exit(send::exit_code(&e));
+                }
+            }
+        }
+        Some(Command::Members { chatroom, json }) => {
+            match members::run(cli.config, &chatroom).await {
+                Ok(list) if json => {
+                    println!("{}", serde_json::to_string_pretty(&list)?);
+                    Ok(())
+                }
+                Ok(list) => {
+                    print!("{}", members::render_table(&list));
+                    Ok(())
+                }
+                Err(e) => {
+                    eprintln!("获取群成员失败 [{}]: {}", e.code(), e);
+                    std::process::exit(send::exit_code(&e));
+                }
+            }
+        }
+        Some(Command::Watch {
+            file,
+            patterns,
+            context,
+            cooldown,
+        }) => {
+            let options = WatchOptions {
+                file,
+                patterns,
+                context,
+                cooldown,
+            };
+            if let Err(e) = watch::run(cli.config, options).await {
+                eprintln!("监视失败 [{}]: {}", e.code(), e);
+                std::process::exit(send::exit_code(&e));
+            }
+            Ok(())
+        }
+        Some(Command::Send { message }) => {
+            let message = match send::read_message(&message, std::io::stdin().lock()) {
+                Ok(message) if !message.trim().is_empty() => message,
+                Ok(_) => {
+                    eprintln!("消息内容为空");
+                    std::process::exit(send::EXIT_FAILURE);
+                }
+                Err(e) => {
+                    eprintln!("读取标准输入失败: {}", e);
+                    std::process::exit(send::EXIT_FAILURE);
+                }
+            };
+            match send::run(cli.config, &message).await {
+                Ok(delivery) => {
+                    println!(
+                        "通知已发送至 {} ({} 段，经由 {})",
+                        delivery.recipient, delivery.parts, delivery.endpoint
+                    );
+                    Ok(())
+                }
+                Err(e) => {
+                    eprintln!("发送失败 [{}]: {}", e.code(), e);
+                    std::process::exit(send::exit_code(&e));
+                }
+            }
+        }
+    }
+}
+
+async fn serve(raw: RawConfig) -> anyhow::Result<()> {
     info!("Starting gewe-notice-mcp MCP 服务器...");
 
-    let config = match Config::parse() {
+    let config = match Config::load(raw.clone()) {
         Ok(cfg) => cfg,
         Err(e) => {
             error!("配置错误: {}", e);
-            error!("请检查您的 MCP 配置文件中的环境变量。");
+            error!("请检查您的 MCP 配置文件中的环境变量或 --config 指定的配置文件。");
             std::process::exit(1);
         }
     };
@@ -40,12 +154,38 @@ async fn main() -> anyhow::Result<()> {
 
     info!("初始化阶段将在会话建立后检查机器人在线状态...");
 
-    info!("配置加载成功 (来自环境变量):");
-    info!("   - Base URL: {}", config.base_url);
-    info!(
-        "   - Token:    {}",
-        config.redact(&config.token_str(), 2, 2)
-    );
+    match &config.config_file {
+        Some(path) => info!("配置加载成功 (来自环境变量与配置文件 {}):", path.display()),
+        None => info!("配置加载成功 (来自环境变量):"),
+    }
+    info!("   - Base URL: {}", config.primary_base_url());
+    if config.base_urls.len() > 1 {
+        info!("   - Fallback: {}", config.base_urls[1..].join(", "));
+    }
+    if let Some(proxy) = &config.transport.proxy {
+        info!("   - Proxy:    {}", proxy);
+    }
+    if !config.transport.ca_certs.is_empty() {
+        info!(
+            "   - CA:       额外信任 {} 个证书",
+            config.transport.ca_certs.len()
+        );
+    }
+    if config.transport.client_identity.is_some() {
+        info!("   - TLS:      使用客户端证书");
+    }
+    if config.token_source.is_refreshable() {
+        info!(
+            "   - Token:    {} (来自{})",
+            config.redact(&config.token_str(), 2, 2),
+            config.token_source
+        );
+    } else {
+        info!(
+            "   - Token:    {}",
+            config.redact(&config.token_str(), 2, 2)
+        );
+    }
     info!(
         "   - App ID:   {}",
         config.redact(config.app_id_str(), 3, 4)
@@ -60,9 +200,27 @@ async fn main() -> anyhow::Result<()> {
             info!("   - At List:  {:?}", redacted_list);
         }
     }
+    if let Some(quiet) = &config.quiet_hours {
+        let windows: Vec<String> = quiet.windows.iter().map(ToString::to_string).collect();
+        info!(
+            "   - 免打扰:   {} ({}，{})",
+            windows.join(", "),
+            quiet.timezone,
+            quiet.mode
+        );
+    }
     info!("{}", "-".repeat(20));
 
+    failover::spawn(api_client.clone());
     let server = GeweNoticeServer::new(api_client);
+    if let Some(path) = &config.config_file {
+        info!(
+            "正在监视配置文件 {}，修改后或收到 SIGHUP 时自动重新加载",
+            path.display()
+        );
+    }
+    reload::spawn(server.clone(), raw);
+    quiet_hours::spawn(server.clone());
     let (stdin, stdout) = stdio();
 
     info!("MCP 服务器已启动，等待连接...");
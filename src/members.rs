@@ -0,0 +1,92 @@
+use crate::config::{Config, RawConfig, WxId};
+use crate::errors::{ConfigValidationError, Result, WxIdValidationError};
+use crate::gewe_api::{ChatroomMember, GeweApiClient};
+use crate::render;
+
+/// 规范化群聊 ID：只给出数字部分时补全 `@chatroom` 后缀。
+pub fn chatroom_id(input: &str) -> Result<WxId> {
+    let input = input.trim();
+    let id = if input.is_empty() || input.contains('@') {
+        input.to_string()
+    } else {
+        format!("{}@chatroom", input)
+    };
+    let wxid = WxId::new(id)?;
+    if !wxid.is_chatroom() {
+        return Err(ConfigValidationError::InvalidWxId {
+            reason: WxIdValidationError::InvalidChatroomFormat,
+        }
+        .into());
+    }
+    Ok(wxid)
+}
+
+/// 渲染为终端表格，群主与管理员排在前面。
+pub fn render_table(members: &[ChatroomMember]) -> String {
+    let mut sorted: Vec<&ChatroomMember> = members.iter().collect();
+    sorted.sort_by_key(|member| (!member.is_owner, !member.is_admin));
+    let rows: Vec<Vec<String>> = sorted
+        .into_iter()
+        .map(|member| {
+            let role = if member.is_owner {
+                "群主"
+            } else if member.is_admin {
+                "管理员"
+            } else {
+                ""
+            };
+            vec![
+                member.display_name.clone().unwrap_or_default(),
+                member.nickname.clone(),
+                role.to_string(),
+                member.wxid.clone(),
+            ]
+        })
+        .collect();
+    render::table(&["群昵称", "昵称", "身份", "wxid"], &rows)
+}
+
+/// `members` 子命令：列出群成员。
+pub async fn run(raw: RawConfig, chatroom: &str) -> Result<Vec<ChatroomMember>> {
+    let chatroom = chatroom_id(chatroom)?;
+    let client = GeweApiClient::new(Config::load_for_lookup(raw)?)?;
+    client.chatroom_members(chatroom.as_str()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chatroom_id_accepts_bare_number() {
+        assert_eq!(chatroom_id("12345").unwrap().as_str(), "12345@chatroom");
+        assert_eq!(
+            chatroom_id(" 12345@chatroom ").unwrap().as_str(),
+            "12345@chatroom"
+        );
+        assert!(chatroom_id("wxid_alice@openim").is_err());
+        assert!(chatroom_id("").is_err());
+    }
+
+    #[test]
+    fn table_lists_owner_and_admins_first() {
+        let member = |wxid: &str, is_owner, is_admin| ChatroomMember {
+            wxid: wxid.into(),
+            nickname: wxid.to_uppercase(),
+            display_name: None,
+            is_owner,
+            is_admin,
+        };
+        let table = render_table(&[
+            member("wxid_c", false, false),
+            member("wxid_b", false, true),
+            member("wxid_a", true, false),
+        ]);
+        let order: Vec<&str> = table
+            .lines()
+            .skip(1)
+            .map(|line| line.split_whitespace().last().unwrap())
+            .collect();
+        assert_eq!(order, vec!["wxid_a", "wxid_b", "wxid_c"]);
+    }
+}
@@ -0,0 +1,434 @@
+use chrono::{DateTime, Local, NaiveTime, Timelike, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::config::Recipient;
+use crate::routing::Severity;
+use crate::server::GeweNoticeServer;
+
+/// 检查免打扰时段是否结束、是否需要发送汇总的间隔。
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 免打扰时段内非紧急通知的处理方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuietMode {
+    /// 暂存，时段结束后汇总为一条消息发送。
+    #[default]
+    Digest,
+    /// 照常发送，但不 @ 任何人。
+    Silent,
+}
+
+impl FromStr for QuietMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "digest" => Ok(Self::Digest),
+            "silent" => Ok(Self::Silent),
+            _ => Err(format!("未知模式 '{}'，可选值: digest, silent", s)),
+        }
+    }
+}
+
+impl fmt::Display for QuietMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Digest => f.write_str("digest"),
+            Self::Silent => f.write_str("silent"),
+        }
+    }
+}
+
+/// 一个每天重复的时段，如 `22:00-08:00`；结束时间早于开始时间表示跨过午夜。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl FromStr for QuietWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|_| format!("时段 '{}' 格式应为 HH:MM-HH:MM，例如 22:00-08:00", s))
+        };
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("时段 '{}' 格式应为 HH:MM-HH:MM，例如 22:00-08:00", s))?;
+        let window = Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        };
+        if window.start == window.end {
+            return Err(format!("时段 '{}' 的开始与结束时间相同", s));
+        }
+        Ok(window)
+    }
+}
+
+impl fmt::Display for QuietWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// 判断免打扰时段所用的时区。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuietTimezone {
+    /// 运行服务器的机器的本地时区。
+    #[default]
+    Local,
+    Named(Tz),
+}
+
+impl QuietTimezone {
+    fn time_of(self, instant: DateTime<Utc>) -> NaiveTime {
+        match self {
+            Self::Local => instant.with_timezone(&Local).time(),
+            Self::Named(tz) => instant.with_timezone(&tz).time(),
+        }
+    }
+
+    /// 按该时区格式化时刻，用于提示与汇总消息。
+    pub fn format(self, instant: DateTime<Utc>, pattern: &str) -> String {
+        match self {
+            Self::Local => instant.with_timezone(&Local).format(pattern).to_string(),
+            Self::Named(tz) => instant.with_timezone(&tz).format(pattern).to_string(),
+        }
+    }
+}
+
+impl FromStr for QuietTimezone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+        s.trim().parse::<Tz>().map(Self::Named).map_err(|_| {
+            format!(
+                "未知时区 '{}'，应为 IANA 时区名 (如 Asia/Shanghai) 或 local",
+                s
+            )
+        })
+    }
+}
+
+impl fmt::Display for QuietTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => f.write_str("local"),
+            Self::Named(tz) => f.write_str(tz.name()),
+        }
+    }
+}
+
+/// 免打扰设置。`critical` 级别的通知不受影响。
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuietHours {
+    pub windows: Vec<QuietWindow>,
+    pub timezone: QuietTimezone,
+    pub mode: QuietMode,
+}
+
+impl QuietHours {
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        let time = self.timezone.time_of(now);
+        self.windows.iter().any(|window| window.contains(time))
+    }
+
+    /// 该严重程度的通知此刻是否应被推迟或静音。
+    pub fn applies(&self, severity: Severity, now: DateTime<Utc>) -> bool {
+        severity != Severity::Critical && self.is_quiet(now)
+    }
+
+    /// 当前免打扰时段 (含首尾相接的多个时段) 的结束时刻；不在时段内或全天免打扰时返回 `None`。
+    pub fn quiet_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if !self.is_quiet(now) {
+            return None;
+        }
+        let start = now.with_second(0)?.with_nanosecond(0)?;
+        (1..=24 * 60)
+            .map(|minutes| start + chrono::Duration::minutes(minutes))
+            .find(|instant| !self.is_quiet(*instant))
+    }
+}
+
+/// 免打扰期间暂存的一条通知。
+#[derive(Debug, Clone)]
+pub struct DeferredNotice {
+    pub recipient: Recipient,
+    pub content: String,
+    pub received_at: DateTime<Utc>,
+}
+
+/// 每个接收者最多暂存的通知数量。超出时丢弃最早的通知，只在汇总中给出丢弃的数量，
+/// 以免 Gewe 长时间不可用时队列无限增长、汇总被拆成几十段。
+pub const DIGEST_CAPACITY: usize = 50;
+
+/// 同一接收者的暂存通知。
+#[derive(Debug, Clone)]
+pub struct DigestGroup {
+    /// 保留的通知，按到达顺序排列。
+    pub notices: Vec<DeferredNotice>,
+    /// 因超出 [`DIGEST_CAPACITY`] 而丢弃的更早的通知数量。
+    pub dropped: usize,
+}
+
+impl DigestGroup {
+    pub fn recipient(&self) -> &Recipient {
+        &self.notices[0].recipient
+    }
+
+    /// 汇总包含的通知总数，含已丢弃的部分。
+    pub fn total(&self) -> usize {
+        self.notices.len() + self.dropped
+    }
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    notices: Vec<DeferredNotice>,
+    /// 按接收者 WXID 统计的丢弃数量。
+    dropped: HashMap<String, usize>,
+}
+
+impl Queue {
+    /// 该接收者的通知超出上限时丢弃最早的几条。
+    fn enforce_capacity(&mut self, wxid: &str) {
+        let mut excess = self
+            .notices
+            .iter()
+            .filter(|notice| notice.recipient.wxid.as_str() == wxid)
+            .count()
+            .saturating_sub(DIGEST_CAPACITY);
+        if excess == 0 {
+            return;
+        }
+        *self.dropped.entry(wxid.to_string()).or_default() += excess;
+        self.notices.retain(|notice| {
+            if excess > 0 && notice.recipient.wxid.as_str() == wxid {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// 暂存通知的内存队列，进程退出时未发送的通知会丢失。
+#[derive(Debug, Clone, Default)]
+pub struct DigestQueue(Arc<Mutex<Queue>>);
+
+impl DigestQueue {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn push(&self, notice: DeferredNotice) {
+        let mut queue = self.lock();
+        let wxid = notice.recipient.wxid.as_str().to_string();
+        queue.notices.push(notice);
+        queue.enforce_capacity(&wxid);
+    }
+
+    /// 队列中保留的通知数量，不含已丢弃的部分。
+    pub fn len(&self) -> usize {
+        self.lock().notices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().notices.is_empty()
+    }
+
+    /// 取出全部通知，按接收者分组并保持到达顺序。
+    pub fn take_grouped(&self) -> Vec<DigestGroup> {
+        let (notices, mut dropped) = {
+            let mut queue = self.lock();
+            (
+                std::mem::take(&mut queue.notices),
+                std::mem::take(&mut queue.dropped),
+            )
+        };
+        let mut groups: Vec<DigestGroup> = Vec::new();
+        for notice in notices {
+            match groups
+                .iter_mut()
+                .find(|group| group.recipient().wxid.as_str() == notice.recipient.wxid.as_str())
+            {
+                Some(group) => group.notices.push(notice),
+                None => groups.push(DigestGroup {
+                    dropped: dropped
+                        .remove(notice.recipient.wxid.as_str())
+                        .unwrap_or_default(),
+                    notices: vec![notice],
+                }),
+            }
+        }
+        groups
+    }
+
+    /// 发送失败的通知放回队首，下次再试；放回后仍受 [`DIGEST_CAPACITY`] 限制。
+    pub fn restore(&self, group: DigestGroup) {
+        let mut queue = self.lock();
+        let wxid = group.recipient().wxid.as_str().to_string();
+        let later = std::mem::replace(&mut queue.notices, group.notices);
+        queue.notices.extend(later);
+        *queue.dropped.entry(wxid.clone()).or_default() += group.dropped;
+        queue.enforce_capacity(&wxid);
+    }
+}
+
+/// 将同一接收者的暂存通知合并为一条汇总消息。
+pub fn render_digest(group: &DigestGroup, timezone: QuietTimezone) -> String {
+    let mut digest = format!("🌙 免打扰期间收到 {} 条通知:", group.total());
+    if group.dropped > 0 {
+        digest.push_str(&format!(
+            "\n(暂存数量超过上限，最早的 {} 条已丢弃，以下为最近 {} 条)",
+            group.dropped,
+            group.notices.len()
+        ));
+    }
+    for notice in &group.notices {
+        digest.push_str(&format!(
+            "\n\n[{}] {}",
+            timezone.format(notice.received_at, "%H:%M"),
+            notice.content
+        ));
+    }
+    digest
+}
+
+/// 在后台定期检查免打扰时段是否结束，结束后发送暂存的汇总通知。
+pub fn spawn(server: GeweNoticeServer) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            ticker.tick().await;
+            server.flush_deferred().await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn quiet(windows: &[&str], timezone: &str) -> QuietHours {
+        QuietHours {
+            windows: windows.iter().map(|w| w.parse().unwrap()).collect(),
+            timezone: timezone.parse().unwrap(),
+            mode: QuietMode::Digest,
+        }
+    }
+
+    #[test]
+    fn windows_wrap_past_midnight() {
+        let window: QuietWindow = "22:00-08:00".parse().unwrap();
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert!(window.contains(at(23, 30)));
+        assert!(window.contains(at(3, 0)));
+        assert!(!window.contains(at(8, 0)));
+        assert!(!window.contains(at(12, 0)));
+
+        assert!("08:00".parse::<QuietWindow>().is_err());
+        assert!("25:00-08:00".parse::<QuietWindow>().is_err());
+        assert!("08:00-08:00".parse::<QuietWindow>().is_err());
+    }
+
+    #[test]
+    fn quiet_hours_use_configured_timezone() {
+        let hours = quiet(&["22:00-08:00"], "Asia/Shanghai");
+        // 15:30 UTC = 23:30 北京时间
+        let night = Utc.with_ymd_and_hms(2026, 3, 1, 15, 30, 0).unwrap();
+        let day = Utc.with_ymd_and_hms(2026, 3, 1, 4, 0, 0).unwrap();
+        assert!(hours.is_quiet(night));
+        assert!(!hours.is_quiet(day));
+        assert!(hours.applies(Severity::Progress, night));
+        assert!(!hours.applies(Severity::Critical, night));
+
+        let until = hours.quiet_until(night).expect("window ends");
+        assert_eq!(until, Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap());
+        assert!(hours.quiet_until(day).is_none());
+
+        assert!("Mars/Olympus".parse::<QuietTimezone>().is_err());
+    }
+
+    #[test]
+    fn digest_queue_keeps_latest_notices_per_recipient() {
+        let notice = |wxid: &str, index: usize| DeferredNotice {
+            recipient: Recipient {
+                wxid: crate::config::WxId::new(wxid.to_string()).unwrap(),
+                at_list: None,
+            },
+            content: format!("notice {index}"),
+            received_at: Utc.with_ymd_and_hms(2026, 3, 1, 23, 0, 0).unwrap(),
+        };
+        let queue = DigestQueue::default();
+        for index in 0..DIGEST_CAPACITY + 5 {
+            queue.push(notice("wxid_a", index));
+        }
+        queue.push(notice("wxid_b", 0));
+        assert_eq!(queue.len(), DIGEST_CAPACITY + 1);
+
+        let mut groups = queue.take_grouped();
+        assert!(queue.is_empty());
+        let group = groups.remove(0);
+        assert_eq!(group.dropped, 5);
+        assert_eq!(group.notices[0].content, "notice 5");
+        assert_eq!(groups[0].dropped, 0);
+        let digest = render_digest(&group, QuietTimezone::default());
+        assert!(digest.starts_with(&format!(
+            "🌙 免打扰期间收到 {} 条通知:",
+            DIGEST_CAPACITY + 5
+        )));
+        assert!(digest.contains("最早的 5 条已丢弃"), "{digest}");
+
+        // 发送失败放回队列后仍受上限约束，丢弃数量累加
+        queue.restore(group);
+        queue.push(notice("wxid_a", DIGEST_CAPACITY + 5));
+        let group = queue.take_grouped().remove(0);
+        assert_eq!(group.notices.len(), DIGEST_CAPACITY);
+        assert_eq!(group.dropped, 6);
+        assert_eq!(group.notices[0].content, "notice 6");
+    }
+
+    #[test]
+    fn adjacent_windows_extend_quiet_period() {
+        let hours = quiet(&["22:00-00:00", "00:00-07:00"], "UTC");
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 23, 15, 20).unwrap();
+        assert_eq!(
+            hours.quiet_until(now),
+            Some(Utc.with_ymd_and_hms(2026, 3, 2, 7, 0, 0).unwrap())
+        );
+    }
+}
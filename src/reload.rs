@@ -0,0 +1,89 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+use crate::config::RawConfig;
+use crate::server::GeweNoticeServer;
+
+/// 检查配置文件是否变化的间隔。
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 在后台监视 `--config` 配置文件，并在文件变化或收到 SIGHUP 时重新加载配置。
+///
+/// `raw` 应为启动时解析的命令行与环境变量（尚未合并配置文件），
+/// 这样重新加载后仍保持 命令行 > 环境变量 > 配置文件 的优先级。
+pub fn spawn(server: GeweNoticeServer, raw: RawConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let path = raw.config.clone();
+        let mut last_seen = path.as_deref().and_then(fingerprint);
+        let mut ticker = tokio::time::interval(WATCH_INTERVAL);
+        let mut hangup = Hangup::new();
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick(), if path.is_some() => {
+                    let current = path.as_deref().and_then(fingerprint);
+                    if current == last_seen {
+                        continue;
+                    }
+                    last_seen = current;
+                    // 编辑器保存时可能先删除再写入，等文件重新出现后再加载
+                    if current.is_some() {
+                        server.reload_config(&raw).await;
+                    }
+                }
+                _ = hangup.recv() => {
+                    tracing::info!("收到 SIGHUP，重新加载配置");
+                    server.reload_config(&raw).await;
+                }
+            }
+        }
+    })
+}
+
+/// 用修改时间与文件大小判断配置文件是否变化。
+fn fingerprint(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(unix)]
+struct Hangup(Option<tokio::signal::unix::Signal>);
+
+#[cfg(unix)]
+impl Hangup {
+    fn new() -> Self {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::hangup()) {
+            Ok(signal) => Self(Some(signal)),
+            Err(e) => {
+                tracing::warn!("无法监听 SIGHUP，仅在配置文件变化时重新加载: {}", e);
+                Self(None)
+            }
+        }
+    }
+
+    async fn recv(&mut self) {
+        match &mut self.0 {
+            Some(signal) => {
+                signal.recv().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct Hangup;
+
+#[cfg(not(unix))]
+impl Hangup {
+    fn new() -> Self {
+        Self
+    }
+
+    async fn recv(&mut self) {
+        std::future::pending().await
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// 结构化通知的状态，对应 README 中推荐的状态表情。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    Warning,
+    Progress,
+}
+
+impl Status {
+    pub const ALL: [Status; 4] = [
+        Status::Success,
+        Status::Failure,
+        Status::Warning,
+        Status::Progress,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+            Self::Warning => "warning",
+            Self::Progress => "progress",
+        }
+    }
+
+    pub fn emoji(self) -> &'static str {
+        match self {
+            Self::Success => "✅",
+            Self::Failure => "❌",
+            Self::Warning => "⚠️",
+            Self::Progress => "📊",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Success => "成功",
+            Self::Failure => "失败",
+            Self::Warning => "警告",
+            Self::Progress => "进度",
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "未知状态 '{}'，可选值: {}",
+                    s,
+                    Self::ALL.map(Status::as_str).join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 一条结构化状态通知，由 `notify_status` 工具构造。
+#[derive(Debug, Clone)]
+pub struct StatusNotice {
+    pub status: Status,
+    pub title: String,
+    pub summary: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl StatusNotice {
+    /// 渲染为统一的微信文本排版：
+    ///
+    /// ```text
+    /// ✅ [标题] 成功
+    /// 摘要
+    ///
+    /// • 键: 值
+    /// ```
+    pub fn render(&self) -> String {
+        let mut lines = vec![format!(
+            "{} [{}] {}",
+            self.status.emoji(),
+            self.title.trim(),
+            self.status.label()
+        )];
+
+        let summary = self.summary.trim();
+        if !summary.is_empty() {
+            lines.push(summary.to_string());
+        }
+
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .filter(|(key, _)| !key.trim().is_empty())
+            .map(|(key, value)| format!("• {}: {}", key.trim(), value.trim()))
+            .collect();
+        if !fields.is_empty() {
+            lines.push(String::new());
+            lines.extend(fields);
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// 分段标记 "(12/34) " 预留的字符数。
+const PART_MARKER_RESERVE: usize = 10;
+
+/// 将超过 `limit` 个字符的内容拆分为多段，并加上 "(1/3) " 形式的序号。
+///
+/// 优先在换行处拆分，其次在句末标点处，最后才按字符硬切。
+/// `first_reserve` 为发送时加在第一段前面的字符数 (如 @ 前缀)，第一段会相应缩短。
+/// 加上前缀后仍未超过上限的内容原样返回为单段；拆分后只剩一段有内容时也不加序号。
+pub fn split_message(content: &str, limit: usize, first_reserve: usize) -> Vec<String> {
+    if content.chars().count() + first_reserve <= limit {
+        return vec![content.to_string()];
+    }
+
+    let budget = limit.saturating_sub(PART_MARKER_RESERVE).max(1);
+    let first_budget = budget.saturating_sub(first_reserve).max(1);
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for segment in content
+        .split_inclusive('\n')
+        .flat_map(split_sentences)
+        .flat_map(|segment| hard_split(segment, budget))
+    {
+        let chunk_budget = if chunks.is_empty() {
+            first_budget
+        } else {
+            budget
+        };
+        let length = segment.chars().count();
+        if !current.is_empty() && current.chars().count() + length > chunk_budget {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if chunks.is_empty() && current.is_empty() && length > first_budget {
+            // 第一段放不下整个片段时，先切出第一段，剩余部分不超过 `budget`
+            let split_at = segment
+                .char_indices()
+                .nth(first_budget)
+                .map_or(segment.len(), |(index, _)| index);
+            chunks.push(segment[..split_at].to_string());
+            current.push_str(&segment[split_at..]);
+            continue;
+        }
+        current.push_str(&segment);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    // 在连续空行处拆分可能得到只有空白的段，编号前去掉，避免发出空的 "(i/n)" 消息
+    let chunks: Vec<&str> = chunks
+        .iter()
+        .map(|chunk| chunk.trim_start_matches(['\r', '\n']).trim_end())
+        .filter(|chunk| !chunk.is_empty())
+        .collect();
+    match chunks.as_slice() {
+        [] => vec![content.to_string()],
+        [only] => vec![only.to_string()],
+        _ => {
+            let total = chunks.len();
+            chunks
+                .iter()
+                .enumerate()
+                .map(|(index, chunk)| format!("({}/{}) {}", index + 1, total, chunk))
+                .collect()
+        }
+    }
+}
+
+fn split_sentences(line: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((index, ch)) = chars.next() {
+        let boundary = match ch {
+            '。' | '！' | '？' | '；' => true,
+            '.' | '!' | '?' | ';' => {
+                !matches!(chars.peek(), Some((_, next)) if !next.is_whitespace())
+            }
+            _ => false,
+        };
+        if boundary {
+            let end = index + ch.len_utf8();
+            sentences.push(&line[start..end]);
+            start = end;
+        }
+    }
+    if start < line.len() {
+        sentences.push(&line[start..]);
+    }
+
+    sentences
+}
+
+fn hard_split(segment: &str, budget: usize) -> Vec<String> {
+    let chars: Vec<char> = segment.chars().collect();
+    chars
+        .chunks(budget)
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+/// 终端中的显示宽度：中日韩字符与全角符号占两列。
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1FAFF => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// 渲染供终端查看的对齐表格，列宽按内容的显示宽度计算。
+pub fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| display_width(h)).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+
+    let line = |cells: Vec<&str>| {
+        let last = cells.len().saturating_sub(1);
+        let mut line = String::new();
+        for (i, (cell, width)) in cells.into_iter().zip(&widths).enumerate() {
+            line.push_str(cell);
+            if i < last {
+                line.push_str(&" ".repeat(width - display_width(cell) + 2));
+            }
+        }
+        line.trim_end().to_string()
+    };
+
+    let mut lines = vec![line(headers.to_vec())];
+    lines.extend(
+        rows.iter()
+            .map(|row| line(row.iter().map(String::as_str).collect())),
+    );
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_aligns_wide_characters() {
+        let rendered = table(
+            &["wxid", "昵称"],
+            &[
+                vec!["wxid_a".into(), "张三".into()],
+                vec!["wxid_long_name".into(), "Bob".into()],
+            ],
+        );
+        assert_eq!(
+            rendered,
+            "wxid            昵称\nwxid_a          张三\nwxid_long_name  Bob\n"
+        );
+    }
+
+    fn notice(status: Status) -> StatusNotice {
+        StatusNotice {
+            status,
+            title: "Build".into(),
+            summary: "编译完成".into(),
+            fields: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn status_parses_case_insensitively() {
+        assert_eq!(" Failure ".parse::<Status>(), Ok(Status::Failure));
+        assert!("done".parse::<Status>().is_err());
+    }
+
+    #[test]
+    fn render_without_fields() {
+        assert_eq!(
+            notice(Status::Success).render(),
+            "✅ [Build] 成功\n编译完成"
+        );
+    }
+
+    #[test]
+    fn render_with_fields_keeps_order() {
+        let mut notice = notice(Status::Warning);
+        notice.fields = vec![
+            ("耗时".into(), "3m12s".into()),
+            ("  ".into(), "ignored".into()),
+            ("warnings".into(), " 4 ".into()),
+        ];
+
+        assert_eq!(
+            notice.render(),
+            "⚠️ [Build] 警告\n编译完成\n\n• 耗时: 3m12s\n• warnings: 4"
+        );
+    }
+
+    #[test]
+    fn render_skips_empty_summary() {
+        let mut notice = notice(Status::Progress);
+        notice.summary = "   ".into();
+        assert_eq!(notice.render(), "📊 [Build] 进度");
+    }
+
+    #[test]
+    fn split_keeps_short_content_intact() {
+        assert_eq!(split_message("短消息", 100, 0), vec!["短消息".to_string()]);
+    }
+
+    #[test]
+    fn split_prefers_line_boundaries() {
+        let content = format!("{}\n{}\n{}", "a".repeat(20), "b".repeat(20), "c".repeat(20));
+        let parts = split_message(&content, 40, 0);
+
+        assert_eq!(
+            parts,
+            vec![
+                format!("(1/3) {}", "a".repeat(20)),
+                format!("(2/3) {}", "b".repeat(20)),
+                format!("(3/3) {}", "c".repeat(20)),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_falls_back_to_sentences_and_hard_cuts() {
+        let content = format!("{}。{}", "甲".repeat(25), "乙".repeat(45));
+        let parts = split_message(&content, 40, 0);
+
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], format!("(1/3) {}。", "甲".repeat(25)));
+        assert_eq!(parts[1], format!("(2/3) {}", "乙".repeat(30)));
+        assert_eq!(parts[2], format!("(3/3) {}", "乙".repeat(15)));
+        assert!(parts.iter().all(|part| part.chars().count() <= 40));
+    }
+
+    #[test]
+    fn split_reserves_room_for_prefix_in_first_part() {
+        let content = "x".repeat(40);
+        assert_eq!(split_message(&content, 40, 0), vec![content.clone()]);
+
+        let parts = split_message(&content, 40, 12);
+        assert_eq!(
+            parts,
+            vec![
+                format!("(1/2) {}", "x".repeat(18)),
+                format!("(2/2) {}", "x".repeat(22)),
+            ]
+        );
+        assert!(parts[0].chars().count() + 12 <= 40);
+    }
+
+    #[test]
+    fn split_drops_whitespace_only_parts() {
+        let content = format!(
+            "{}\n{}\n{}",
+            "a".repeat(30),
+            "\n".repeat(45),
+            "b".repeat(30)
+        );
+        let parts = split_message(&content, 40, 0);
+        assert_eq!(
+            parts,
+            vec![
+                format!("(1/2) {}", "a".repeat(30)),
+                format!("(2/2) {}", "b".repeat(30)),
+            ]
+        );
+
+        // 只剩一段有内容时不加序号
+        let content = format!("{}{}", "a".repeat(30), "\n".repeat(45));
+        assert_eq!(split_message(&content, 40, 0), vec!["a".repeat(30)]);
+    }
+
+    #[test]
+    fn split_does_not_break_inside_decimal_numbers() {
+        assert_eq!(split_sentences("v1.2 ok. done"), vec!["v1.2 ok.", " done"]);
+    }
+}
@@ -0,0 +1,329 @@
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::{Config, Recipient, WxId};
+use crate::render::Status;
+
+/// 路由规则中可引用的默认接收者别名。
+pub const DEFAULT_ROUTE_TARGET: &str = "default";
+
+/// 通知的严重程度，用于路由规则匹配。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Progress,
+    Success,
+    Warning,
+    Failure,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 6] = [
+        Severity::Info,
+        Severity::Progress,
+        Severity::Success,
+        Severity::Warning,
+        Severity::Failure,
+        Severity::Critical,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Progress => "progress",
+            Self::Success => "success",
+            Self::Warning => "warning",
+            Self::Failure => "failure",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl From<Status> for Severity {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Success => Self::Success,
+            Status::Failure => Self::Failure,
+            Status::Warning => Self::Warning,
+            Status::Progress => Self::Progress,
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|severity| severity.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "未知严重程度 '{}'，可选值: {}",
+                    s,
+                    Self::ALL.map(Severity::as_str).join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 参与路由匹配的消息属性。
+#[derive(Debug, Clone)]
+pub struct RouteContext {
+    pub severity: Severity,
+    pub tags: Vec<String>,
+    pub source: Option<String>,
+}
+
+/// 一条路由规则。所有非空的条件都满足时命中：
+/// `severity` 包含消息的严重程度、`tags` 与消息标签有交集、`source` 包含消息来源。
+#[derive(Debug, Clone)]
+pub struct RouteRule {
+    pub name: String,
+    pub severity: Vec<Severity>,
+    pub tags: Vec<String>,
+    pub source: Vec<String>,
+    /// 具名接收者别名，`default` 表示默认接收者。
+    pub recipients: Vec<String>,
+    /// 覆盖接收者自身的 @ 列表；空列表表示不 @ 任何人。
+    pub at_list: Option<Vec<WxId>>,
+}
+
+impl RouteRule {
+    pub fn matches(&self, context: &RouteContext) -> bool {
+        let severity = self.severity.is_empty() || self.severity.contains(&context.severity);
+        let tags = self.tags.is_empty() || self.tags.iter().any(|tag| context.tags.contains(tag));
+        let source = self.source.is_empty()
+            || matches!(&context.source, Some(source) if self.source.contains(source));
+
+        severity && tags && source
+    }
+}
+
+/// 路由结果是如何得出的。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteReason {
+    /// 调用方通过 `to` 或 `recipient` 显式指定了接收者，未经过规则。
+    Explicit,
+    /// 命中了某条路由规则。
+    Rule,
+    /// 没有规则命中，发送给默认接收者。
+    Default,
+}
+
+/// 一次路由决策，会记录到 MCP 日志并返回给调用方。
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingDecision {
+    pub reason: RouteReason,
+    /// 命中的规则名称。
+    pub rule: Option<String>,
+    pub severity: Severity,
+    pub tags: Vec<String>,
+    pub source: Option<String>,
+    /// 最终接收者的 WXID，按发送顺序排列。
+    pub recipients: Vec<String>,
+}
+
+impl RoutingDecision {
+    pub fn explicit(context: RouteContext, recipient: &Recipient) -> Self {
+        Self::new(
+            RouteReason::Explicit,
+            None,
+            context,
+            std::slice::from_ref(recipient),
+        )
+    }
+
+    fn new(
+        reason: RouteReason,
+        rule: Option<String>,
+        context: RouteContext,
+        recipients: &[Recipient],
+    ) -> Self {
+        Self {
+            reason,
+            rule,
+            severity: context.severity,
+            tags: context.tags,
+            source: context.source,
+            recipients: recipients
+                .iter()
+                .map(|recipient| recipient.wxid.as_str().to_string())
+                .collect(),
+        }
+    }
+
+    /// 用于日志的简短描述。
+    pub fn describe(&self) -> String {
+        let target = self.recipients.join(", ");
+        let attributes = format!(
+            "severity={}, tags=[{}], source={}",
+            self.severity,
+            self.tags.join(", "),
+            self.source.as_deref().unwrap_or("-")
+        );
+        match self.reason {
+            RouteReason::Explicit => format!("显式指定接收者 {} ({})", target, attributes),
+            RouteReason::Rule => format!(
+                "命中路由规则 '{}' ({}) -> {}",
+                self.rule.as_deref().unwrap_or_default(),
+                attributes,
+                target
+            ),
+            RouteReason::Default => {
+                format!("未命中路由规则 ({}) -> 默认接收者 {}", attributes, target)
+            }
+        }
+    }
+}
+
+/// 按顺序评估 `config.routes`，第一条命中的规则决定接收者；
+/// 均未命中时发送给默认接收者。
+pub fn route(config: &Config, context: RouteContext) -> (Vec<Recipient>, RoutingDecision) {
+    let Some(rule) = config.routes.iter().find(|rule| rule.matches(&context)) else {
+        let recipients = vec![config.default_recipient()];
+        let decision = RoutingDecision::new(RouteReason::Default, None, context, &recipients);
+        return (recipients, decision);
+    };
+
+    let recipients: Vec<Recipient> = rule
+        .recipients
+        .iter()
+        .filter_map(|alias| {
+            if alias == DEFAULT_ROUTE_TARGET {
+                Some(config.default_recipient())
+            } else {
+                config.recipient_by_alias(alias)
+            }
+        })
+        .map(|mut recipient| {
+            if let Some(at_list) = &rule.at_list {
+                recipient.at_list = (!at_list.is_empty()).then(|| at_list.clone());
+            }
+            recipient
+        })
+        .collect();
+
+    let decision = RoutingDecision::new(
+        RouteReason::Rule,
+        Some(rule.name.clone()),
+        context,
+        &recipients,
+    );
+    (recipients, decision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppId, ValidatedToken};
+
+    fn wxid(value: &str) -> WxId {
+        WxId::new(value.to_string()).expect("valid wxid")
+    }
+
+    fn rule(name: &str, severity: &[Severity], tags: &[&str], recipients: &[&str]) -> RouteRule {
+        RouteRule {
+            name: name.to_string(),
+            severity: severity.to_vec(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            source: Vec::new(),
+            recipients: recipients.iter().map(|alias| alias.to_string()).collect(),
+            at_list: None,
+        }
+    }
+
+    fn config(routes: Vec<RouteRule>) -> Config {
+        Config {
+            base_urls: vec!["http://example.com".into()],
+            token: ValidatedToken::new("00000000-0000-0000-0000-000000000000").unwrap(),
+            token_source: Default::default(),
+            app_id: AppId::new("wx_test".to_string()).unwrap(),
+            wxid: wxid("wxid_me"),
+            at_list: None,
+            allowed_recipients: Vec::new(),
+            max_message_length: crate::config::DEFAULT_MAX_MESSAGE_LENGTH,
+            http: Default::default(),
+            transport: Default::default(),
+            quiet_hours: None,
+            exec: Default::default(),
+            recipients: [(
+                "ops".to_string(),
+                Recipient {
+                    wxid: wxid("ops@chatroom"),
+                    at_list: Some(vec![wxid("wxid_oncall")]),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            routes,
+            config_file: None,
+        }
+    }
+
+    fn context(severity: Severity, tags: &[&str]) -> RouteContext {
+        RouteContext {
+            severity,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let config = config(vec![
+            rule(
+                "failures",
+                &[Severity::Failure, Severity::Critical],
+                &[],
+                &["ops"],
+            ),
+            rule("deploys", &[], &["deploy"], &["ops", "default"]),
+        ]);
+
+        let (recipients, decision) = route(&config, context(Severity::Failure, &["deploy"]));
+        assert_eq!(decision.reason, RouteReason::Rule);
+        assert_eq!(decision.rule.as_deref(), Some("failures"));
+        assert_eq!(decision.recipients, vec!["ops@chatroom"]);
+        assert!(recipients[0].at_list.is_some());
+
+        let (_, decision) = route(&config, context(Severity::Success, &["deploy"]));
+        assert_eq!(decision.rule.as_deref(), Some("deploys"));
+        assert_eq!(decision.recipients, vec!["ops@chatroom", "wxid_me"]);
+    }
+
+    #[test]
+    fn unmatched_messages_go_to_default_recipient() {
+        let config = config(vec![rule("failures", &[Severity::Failure], &[], &["ops"])]);
+
+        let (recipients, decision) = route(&config, context(Severity::Progress, &[]));
+        assert_eq!(decision.reason, RouteReason::Default);
+        assert_eq!(recipients.len(), 1);
+        assert_eq!(recipients[0].wxid.as_str(), "wxid_me");
+    }
+
+    #[test]
+    fn rule_at_list_overrides_profile() {
+        let mut quiet = rule("quiet", &[], &[], &["ops"]);
+        quiet.at_list = Some(Vec::new());
+        quiet.source = vec!["nightly".into()];
+        let config = config(vec![quiet]);
+
+        let mut ctx = context(Severity::Info, &[]);
+        assert_eq!(route(&config, ctx.clone()).1.reason, RouteReason::Default);
+
+        ctx.source = Some("nightly".into());
+        let (recipients, _) = route(&config, ctx);
+        assert!(recipients[0].at_list.is_none());
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::Read;
+
+use crate::config::{Config, RawConfig};
+use crate::errors::{GeweNoticeError, Result};
+use crate::gewe_api::{Delivery, GeweApiClient};
+
+/// 配置无效 (sysexits `EX_CONFIG`)，修正配置前重试没有意义。
+pub const EXIT_CONFIG: i32 = 78;
+/// 临时故障 (sysexits `EX_TEMPFAIL`)，稍后重试可能成功。
+pub const EXIT_RETRYABLE: i32 = 75;
+/// 需要人工处理的故障 (sysexits `EX_UNAVAILABLE`)，如机器人离线、不在群内。
+pub const EXIT_FATAL: i32 = 69;
+/// 其他失败。
+pub const EXIT_FAILURE: i32 = 1;
+
+/// `send` 子命令失败时的退出码，供脚本区分是否值得重试。
+/// `contacts`、`members` 与 `watch` 子命令失败时使用相同的退出码。
+pub fn exit_code(err: &GeweNoticeError) -> i32 {
+    if matches!(err, GeweNoticeError::Config(_)) {
+        EXIT_CONFIG
+    } else if err.is_retryable() {
+        EXIT_RETRYABLE
+    } else if err.is_fatal() {
+        EXIT_FATAL
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+/// 取得要发送的消息；参数为 `-` 时从 `stdin` 读取全部内容并去掉末尾换行。
+pub fn read_message(arg: &str, stdin: impl Read) -> std::io::Result<String> {
+    if arg != "-" {
+        return Ok(arg.to_string());
+    }
+    let mut message = String::new();
+    let mut stdin = stdin;
+    stdin.read_to_string(&mut message)?;
+    Ok(message.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// 使用与 MCP 服务器相同的配置与 @ 逻辑，向默认接收者发送一条文本通知。
+pub async fn run(raw: RawConfig, message: &str) -> Result<Delivery> {
+    let config = Config::load(raw)?;
+    let client = GeweApiClient::new(config)?;
+    client.post_text(message).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::{ApiBusinessError, ConfigValidationError, NetworkError};
+
+    #[test]
+    fn exit_codes_distinguish_config_retryable_and_fatal() {
+        let config: GeweNoticeError = ConfigValidationError::ConflictingTokenSources.into();
+        assert_eq!(exit_code(&config), EXIT_CONFIG);
+
+        let retryable: GeweNoticeError = NetworkError::ConnectionRefused.into();
+        assert!(retryable.is_retryable());
+        assert_eq!(exit_code(&retryable), EXIT_RETRYABLE);
+
+        let fatal: GeweNoticeError = ApiBusinessError::BotOffline.into();
+        assert!(fatal.is_fatal());
+        assert_eq!(exit_code(&fatal), EXIT_FATAL);
+    }
+
+    #[test]
+    fn dash_reads_message_from_stdin() {
+        assert_eq!(
+            read_message("hello", "ignored".as_bytes()).unwrap(),
+            "hello"
+        );
+        assert_eq!(
+            read_message("-", "line one\nline two\n".as_bytes()).unwrap(),
+            "line one\nline two"
+        );
+    }
+}
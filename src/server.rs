@@ -1,10 +1,11 @@
+use chrono::Utc;
 use rmcp::{
     handler::server::ServerHandler,
     model::{
         CallToolRequestMethod, CallToolRequestParam, CallToolResult, Content, ErrorData,
         Implementation, InitializeRequestParam, InitializeResult, JsonObject, ListToolsResult,
-        LoggingLevel, LoggingMessageNotificationParam, PaginatedRequestParam, ServerCapabilities,
-        SetLevelRequestParam, Tool, ToolsCapability,
+        LoggingLevel, LoggingMessageNotificationParam, Meta, PaginatedRequestParam,
+        ServerCapabilities, SetLevelRequestParam, Tool, ToolAnnotations, ToolsCapability,
     },
     service::{RequestContext, RoleServer},
 };
@@ -18,13 +19,27 @@ use std::sync::{
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 
-use crate::gewe_api::GeweApiClient;
+use crate::config::{Config, RawConfig, Recipient, WxId};
+use crate::contacts::ContactFilter;
+use crate::errors::GeweNoticeError;
+use crate::gewe_api::{
+    validate_file_name, ContactKind, Delivery, GeweApiClient, LinkMessage, ALLOWED_FILE_EXTENSIONS,
+    MAX_FILE_SIZE, SENT_HISTORY_CAPACITY,
+};
+use crate::quiet_hours::{self, DeferredNotice, DigestQueue, QuietHours, QuietMode};
+use crate::render::{Status, StatusNotice};
+use crate::routing::{self, RouteContext, RoutingDecision, Severity};
+
+/// `list_contacts` 默认最多返回的联系人数量，避免大通讯录撑满上下文。
+const DEFAULT_CONTACT_LIMIT: usize = 200;
 
 #[derive(Clone)]
 pub struct GeweNoticeServer {
     api_client: Arc<GeweApiClient>,
     peer: Arc<RwLock<Option<rmcp::service::Peer<RoleServer>>>>,
     min_log_level: Arc<AtomicU8>,
+    /// 免打扰期间暂存、等待汇总发送的通知。
+    deferred: DigestQueue,
     #[cfg(test)]
     log_tap: Arc<Mutex<Vec<LoggingMessageNotificationParam>>>,
 }
@@ -35,6 +50,7 @@ impl GeweNoticeServer {
             api_client: Arc::new(api_client),
             peer: Arc::new(RwLock::new(None)),
             min_log_level: Arc::new(AtomicU8::new(Self::level_value(LoggingLevel::Info))),
+            deferred: DigestQueue::default(),
             #[cfg(test)]
             log_tap: Arc::new(Mutex::new(Vec::new())),
         }
@@ -81,6 +97,7 @@ impl GeweNoticeServer {
             api_client: Arc::new(api_client),
             peer: Arc::new(RwLock::new(None)),
             min_log_level: Arc::new(AtomicU8::new(Self::level_value(LoggingLevel::Info))),
+            deferred: DigestQueue::default(),
             log_tap: Arc::new(Mutex::new(Vec::new())),
         };
         let tap = server.log_tap.clone();
@@ -164,6 +181,78 @@ impl GeweNoticeServer {
         }
     }
 
+    /// 重新读取配置文件并校验，通过后原子替换生效配置；
+    /// 校验失败时保留当前配置，并通过 MCP 日志发出警告。返回是否完成了替换。
+    pub async fn reload_config(&self, raw: &RawConfig) -> bool {
+        let Some(path) = &raw.config else {
+            self.emit_log_message(
+                LoggingLevel::Warning,
+                "未指定 --config 配置文件，忽略重新加载请求",
+            )
+            .await;
+            return false;
+        };
+
+        // 加载配置可能运行 Token 命令，放到阻塞线程池中执行
+        let loaded = {
+            let raw = raw.clone();
+            tokio::task::spawn_blocking(move || Config::load(raw)).await
+        };
+        let loaded = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.emit_log_message(
+                    LoggingLevel::Warning,
+                    format!("重新加载配置的任务异常，继续使用当前配置: {}", e),
+                )
+                .await;
+                return false;
+            }
+        };
+        let replaced = loaded.and_then(|config| {
+            let tools_changed =
+                self.api_client.config().recipient_aliases() != config.recipient_aliases();
+            self.api_client
+                .replace_config(config)
+                .map(|()| tools_changed)
+        });
+        match replaced {
+            Ok(tools_changed) => {
+                self.emit_log_message(
+                    LoggingLevel::Info,
+                    format!("已重新加载配置文件 {}", path.display()),
+                )
+                .await;
+
+                if tools_changed {
+                    self.emit_log_message(
+                        LoggingLevel::Info,
+                        "具名接收者已变化，通知客户端刷新工具列表",
+                    )
+                    .await;
+                    self.notify_tool_list_changed().await;
+                }
+                true
+            }
+            Err(e) => {
+                self.emit_log_message(
+                    LoggingLevel::Warning,
+                    format!("重新加载配置失败，继续使用当前配置: {}", e),
+                )
+                .await;
+                false
+            }
+        }
+    }
+
+    async fn notify_tool_list_changed(&self) {
+        if let Some(peer) = self.peer.read().await.clone() {
+            if let Err(err) = peer.notify_tool_list_changed().await {
+                tracing::debug!(error = %err, "unable to send tools/list_changed notification");
+            }
+        }
+    }
+
     async fn set_peer(&self, peer: rmcp::service::Peer<RoleServer>) {
         self.peer.write().await.replace(peer);
     }
@@ -180,29 +269,1226 @@ impl GeweNoticeServer {
         let content = params["content"]
             .as_str()
             .ok_or_else(|| ErrorData::invalid_params("content parameter is required", None))?;
+        let severity = Self::severity_param(&params, Severity::Info)?;
+        let (recipients, decision) = self.route_message(&params, severity)?;
+
+        self.deliver_text(&recipients, decision, content).await
+    }
+
+    /// 可选的 `severity` 参数，未提供时使用 `default`。
+    fn severity_param(
+        params: &serde_json::Value,
+        default: Severity,
+    ) -> Result<Severity, ErrorData> {
+        match &params["severity"] {
+            Value::Null => Ok(default),
+            Value::String(severity) => severity
+                .parse::<Severity>()
+                .map_err(|e| ErrorData::invalid_params(e, None)),
+            _ => Err(ErrorData::invalid_params("severity must be a string", None)),
+        }
+    }
+
+    async fn handle_notify_status(
+        &self,
+        params: serde_json::Value,
+    ) -> Result<CallToolResult, ErrorData> {
+        let status = params["status"]
+            .as_str()
+            .ok_or_else(|| ErrorData::invalid_params("status parameter is required", None))?
+            .parse::<Status>()
+            .map_err(|e| ErrorData::invalid_params(e, None))?;
+        let title = params["title"]
+            .as_str()
+            .ok_or_else(|| ErrorData::invalid_params("title parameter is required", None))?;
+        let summary = params["summary"].as_str().unwrap_or_default();
+
+        let fields = match &params["fields"] {
+            Value::Null => Vec::new(),
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+            _ => return Err(ErrorData::invalid_params("fields must be an object", None)),
+        };
+
+        let notice = StatusNotice {
+            status,
+            title: title.to_string(),
+            summary: summary.to_string(),
+            fields,
+        };
+        let severity = Self::severity_param(&params, status.into())?;
+        let (recipients, decision) = self.route_message(&params, severity)?;
+
+        self.deliver_text(&recipients, decision, &notice.render())
+            .await
+    }
+
+    /// 路由层：显式的 `to`/`recipient` 优先，否则按配置的路由规则选择接收者。
+    fn route_message(
+        &self,
+        params: &serde_json::Value,
+        severity: Severity,
+    ) -> Result<(Vec<Recipient>, RoutingDecision), ErrorData> {
+        let tags = match &params["tags"] {
+            Value::Null => Vec::new(),
+            Value::Array(tags) => tags
+                .iter()
+                .map(|tag| {
+                    tag.as_str()
+                        .map(|tag| tag.trim().to_string())
+                        .ok_or_else(|| {
+                            ErrorData::invalid_params("tags entries must be strings", None)
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(ErrorData::invalid_params("tags must be an array", None)),
+        };
+        let source = match &params["source"] {
+            Value::Null => None,
+            Value::String(source) => Some(source.trim().to_string()),
+            _ => return Err(ErrorData::invalid_params("source must be a string", None)),
+        };
+        let context = RouteContext {
+            severity,
+            tags,
+            source,
+        };
+
+        if !params["to"].is_null() || !params["recipient"].is_null() {
+            let recipient = self.resolve_recipient(params)?;
+            let decision = RoutingDecision::explicit(context, &recipient);
+            return Ok((vec![recipient], decision));
+        }
+
+        let (mut recipients, decision) = routing::route(&self.api_client.config(), context);
+        if let Some(at_list) = Self::parse_at_override(params)? {
+            for recipient in &mut recipients {
+                recipient.at_list = (!at_list.is_empty()).then(|| at_list.clone());
+            }
+        }
+        Ok((recipients, decision))
+    }
 
-        self.emit_log_message(LoggingLevel::Info, format!("收到发送通知请求: {}", content))
+    async fn deliver_text(
+        &self,
+        recipients: &[Recipient],
+        decision: RoutingDecision,
+        content: &str,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.emit_log_message(
+            LoggingLevel::Info,
+            format!("通知路由: {}", decision.describe()),
+        )
+        .await;
+        self.emit_log_message(
+            LoggingLevel::Info,
+            format!(
+                "收到发送通知请求 (接收者 {}): {}",
+                decision.recipients.join(", "),
+                content
+            ),
+        )
+        .await;
+
+        let config = self.api_client.config();
+        let now = Utc::now();
+        let quiet = config
+            .quiet_hours
+            .as_ref()
+            .filter(|quiet| quiet.applies(decision.severity, now));
+        let silenced: Vec<Recipient>;
+        let recipients = match quiet {
+            Some(quiet) if quiet.mode == QuietMode::Digest => {
+                return self
+                    .defer_text(recipients, Some(decision), content, quiet)
+                    .await;
+            }
+            Some(_) => {
+                silenced = recipients
+                    .iter()
+                    .map(|recipient| Recipient {
+                        wxid: recipient.wxid.clone(),
+                        at_list: None,
+                    })
+                    .collect();
+                &silenced[..]
+            }
+            None => recipients,
+        };
+
+        let mut deliveries = Vec::with_capacity(recipients.len());
+        for (index, recipient) in recipients.iter().enumerate() {
+            match self.api_client.post_text_to(recipient, content).await {
+                Ok(delivery) => deliveries.push(delivery),
+                Err(e) => {
+                    let action = if deliveries.is_empty() {
+                        format!("发送通知失败 (接收者 {})", recipient.wxid)
+                    } else {
+                        let delivered: Vec<&str> = deliveries
+                            .iter()
+                            .map(|delivery: &Delivery| delivery.recipient.as_str())
+                            .collect();
+                        format!(
+                            "发送通知失败 (接收者 {}，已送达: {})",
+                            recipient.wxid,
+                            delivered.join(", ")
+                        )
+                    };
+                    let mut result = self.tool_failure(&action, &e).await;
+                    if recipients.len() > 1 {
+                        // 已送达的接收者不应重发，Agent 只能针对失败与未尝试的接收者重试
+                        let error = result
+                            .meta
+                            .as_mut()
+                            .and_then(|meta| meta.0.get_mut("error"))
+                            .and_then(Value::as_object_mut);
+                        if let Some(error) = error {
+                            let skipped: Vec<&str> = recipients[index + 1..]
+                                .iter()
+                                .map(|recipient| recipient.wxid.as_str())
+                                .collect();
+                            let partial = !deliveries.is_empty();
+                            error.insert("partial".into(), Value::Bool(partial));
+                            if partial {
+                                // 整体重发会再次通知已送达的接收者，即使失败原因本身可以重试
+                                error.insert("retryable".into(), Value::Bool(false));
+                                error.insert("retry_after_ms".into(), Value::Null);
+                            }
+                            error.insert("failed_recipient".into(), recipient.wxid.as_str().into());
+                            error.insert("skipped".into(), serde_json::json!(skipped));
+                            error.insert(
+                                "deliveries".into(),
+                                serde_json::to_value(&deliveries).unwrap_or_default(),
+                            );
+                        }
+                    }
+                    return Ok(result);
+                }
+            }
+        }
+
+        self.emit_log_message(LoggingLevel::Info, format!("通知发送成功: {}", content))
             .await;
+        let Some(first) = deliveries.first() else {
+            return Err(ErrorData::internal_error("路由结果没有接收者", None));
+        };
+        let mut text = if first.parts > 1 {
+            format!("通知已分 {} 段成功发送: {}", first.parts, content)
+        } else {
+            format!("通知已成功发送: {}", content)
+        };
+        for delivery in &deliveries {
+            if deliveries.len() > 1 {
+                text.push_str(&format!("\n- {}", delivery.recipient));
+            }
+            text.push_str(&Self::message_id_suffix(delivery));
+        }
 
-        match self.api_client.post_text(content).await {
-            Ok(_) => {
-                self.emit_log_message(LoggingLevel::Info, format!("通知发送成功: {}", content))
+        if quiet.is_some() {
+            text.push_str("\n免打扰时段内，已取消 @ 提醒");
+        }
+
+        let mut structured = serde_json::to_value(first).ok();
+        if let Some(Value::Object(map)) = &mut structured {
+            map.insert(
+                "routing".to_string(),
+                serde_json::to_value(&decision).unwrap_or_default(),
+            );
+            map.insert("deferred".to_string(), Value::Bool(false));
+            if let Some(quiet) = quiet {
+                map.insert(
+                    "quiet_hours".to_string(),
+                    serde_json::to_value(quiet.mode).unwrap_or_default(),
+                );
+            }
+            if deliveries.len() > 1 {
+                map.insert(
+                    "deliveries".to_string(),
+                    serde_json::to_value(&deliveries).unwrap_or_default(),
+                );
+            }
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: None,
+            meta: None,
+            structured_content: structured,
+        })
+    }
+
+    /// 图片、文件与链接没有严重级别，按非紧急通知判断是否处于免打扰时段。
+    fn media_quiet_hours(&self) -> Option<QuietHours> {
+        self.api_client
+            .config()
+            .quiet_hours
+            .clone()
+            .filter(|quiet| quiet.applies(Severity::Info, Utc::now()))
+    }
+
+    /// 免打扰时段内暂存通知，待时段结束后由 [`Self::flush_deferred`] 汇总发送。
+    /// 图片、文件与链接没有路由决策，以文字描述暂存。
+    async fn defer_text(
+        &self,
+        recipients: &[Recipient],
+        decision: Option<RoutingDecision>,
+        content: &str,
+        quiet: &QuietHours,
+    ) -> Result<CallToolResult, ErrorData> {
+        let now = Utc::now();
+        for recipient in recipients {
+            self.deferred.push(DeferredNotice {
+                recipient: recipient.clone(),
+                content: content.to_string(),
+                received_at: now,
+            });
+        }
+        let deliver_after = quiet.quiet_until(now);
+        let text = match deliver_after {
+            Some(until) => format!(
+                "当前处于免打扰时段，通知已暂存，将于 {} 后汇总发送: {}",
+                quiet.timezone.format(until, "%H:%M"),
+                content
+            ),
+            None => format!("当前处于免打扰时段，通知已暂存: {}", content),
+        };
+        self.emit_log_message(LoggingLevel::Info, text.clone())
+            .await;
+
+        let Some(first) = recipients.first() else {
+            return Err(ErrorData::internal_error("路由结果没有接收者", None));
+        };
+        let placeholder = Delivery {
+            recipient: first.wxid.as_str().to_string(),
+            parts: 0,
+            messages: Vec::new(),
+            contents: Vec::new(),
+            mentioned: Vec::new(),
+            at_all_fallback: false,
+            attempts: 0,
+            elapsed_ms: 0,
+            endpoint: String::new(),
+            warning: None,
+        };
+        let mut structured = serde_json::to_value(&placeholder).ok();
+        if let Some(Value::Object(map)) = &mut structured {
+            if let Some(decision) = decision {
+                map.insert(
+                    "routing".to_string(),
+                    serde_json::to_value(&decision).unwrap_or_default(),
+                );
+            }
+            map.insert("deferred".to_string(), Value::Bool(true));
+            map.insert(
+                "deliver_after".to_string(),
+                deliver_after
+                    .map(|until| Value::String(until.to_rfc3339()))
+                    .unwrap_or(Value::Null),
+            );
+            map.insert(
+                "quiet_hours".to_string(),
+                serde_json::to_value(quiet.mode).unwrap_or_default(),
+            );
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: None,
+            meta: None,
+            structured_content: structured,
+        })
+    }
+
+    /// 免打扰时段结束后，按接收者将暂存的通知合并为一条消息发送；
+    /// 临时故障时通知放回队列下次再试，无法送达时丢弃。返回成功送达的通知数量。
+    pub async fn flush_deferred(&self) -> usize {
+        if self.deferred.is_empty() {
+            return 0;
+        }
+        let config = self.api_client.config();
+        if let Some(quiet) = &config.quiet_hours {
+            if quiet.is_quiet(Utc::now()) {
+                return 0;
+            }
+        }
+        let timezone = config
+            .quiet_hours
+            .as_ref()
+            .map(|quiet| quiet.timezone)
+            .unwrap_or_default();
+
+        let mut flushed = 0;
+        for group in self.deferred.take_grouped() {
+            let recipient = group.recipient().clone();
+            let digest = quiet_hours::render_digest(&group, timezone);
+            match self.api_client.post_text_to(&recipient, &digest).await {
+                Ok(_) => {
+                    flushed += group.notices.len();
+                    self.emit_log_message(
+                        LoggingLevel::Info,
+                        format!(
+                            "免打扰时段结束，已向 {} 汇总发送 {} 条通知",
+                            recipient.wxid,
+                            group.total()
+                        ),
+                    )
+                    .await;
+                }
+                Err(e) if e.is_fatal() => {
+                    // 重试也无法送达 (如机器人不在群内)，丢弃以免每次检查都重复告警
+                    self.emit_log_message(
+                        LoggingLevel::Error,
+                        format!(
+                            "汇总通知无法送达，已丢弃 (接收者 {}，{} 条): {}",
+                            recipient.wxid,
+                            group.total(),
+                            e
+                        ),
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    self.emit_log_message(
+                        LoggingLevel::Warning,
+                        format!(
+                            "汇总通知发送失败 (接收者 {}，{} 条)，稍后重试: {}",
+                            recipient.wxid,
+                            group.total(),
+                            e
+                        ),
+                    )
+                    .await;
+                    self.deferred.restore(group);
+                }
+            }
+        }
+        flushed
+    }
+
+    async fn handle_post_image(
+        &self,
+        params: serde_json::Value,
+    ) -> Result<CallToolResult, ErrorData> {
+        let image_url = params["image_url"]
+            .as_str()
+            .ok_or_else(|| ErrorData::invalid_params("image_url parameter is required", None))?;
+        Self::ensure_http_url("image_url", image_url)?;
+
+        self.emit_log_message(
+            LoggingLevel::Info,
+            format!("收到发送图片请求: {}", image_url),
+        )
+        .await;
+
+        if let Some(quiet) = self.media_quiet_hours() {
+            if quiet.mode == QuietMode::Digest {
+                let recipient = self.api_client.config().default_recipient();
+                let notice = format!("🖼️ 图片: {}", image_url);
+                return self.defer_text(&[recipient], None, &notice, &quiet).await;
+            }
+        }
+
+        match self.api_client.post_image(image_url).await {
+            Ok(delivery) => {
+                self.emit_log_message(LoggingLevel::Info, format!("图片发送成功: {}", image_url))
                     .await;
                 Ok(CallToolResult {
-                    content: vec![Content::text(format!("通知已成功发送: {}", content))],
+                    content: vec![Content::text(format!(
+                        "图片已成功发送: {}{}",
+                        image_url,
+                        Self::message_id_suffix(&delivery)
+                    ))],
                     is_error: None,
                     meta: None,
-                    structured_content: None,
+                    structured_content: serde_json::to_value(&delivery).ok(),
                 })
             }
-            Err(e) => {
-                self.emit_log_message(LoggingLevel::Error, format!("发送通知失败: {}", e))
+            Err(e) => Ok(self.tool_failure("发送图片失败", &e).await),
+        }
+    }
+
+    async fn handle_post_file(
+        &self,
+        params: serde_json::Value,
+    ) -> Result<CallToolResult, ErrorData> {
+        let file_name = params["file_name"]
+            .as_str()
+            .ok_or_else(|| ErrorData::invalid_params("file_name parameter is required", None))?;
+        let file_url = params["file_url"]
+            .as_str()
+            .ok_or_else(|| ErrorData::invalid_params("file_url parameter is required", None))?;
+        Self::ensure_http_url("file_url", file_url)?;
+
+        self.emit_log_message(
+            LoggingLevel::Info,
+            format!("收到发送文件请求: {} ({})", file_name, file_url),
+        )
+        .await;
+
+        if let Some(quiet) = self.media_quiet_hours() {
+            if quiet.mode == QuietMode::Digest {
+                if let Err(e) = validate_file_name(file_name) {
+                    return Ok(self.tool_failure("发送文件失败", &e).await);
+                }
+                let recipient = self.api_client.config().default_recipient();
+                let notice = format!("📎 文件 {}: {}", file_name, file_url);
+                return self.defer_text(&[recipient], None, &notice, &quiet).await;
+            }
+        }
+
+        match self.api_client.post_file(file_name, file_url).await {
+            Ok(delivery) => {
+                self.emit_log_message(LoggingLevel::Info, format!("文件发送成功: {}", file_name))
                     .await;
-                Err(ErrorData::internal_error(
-                    format!("发送通知失败: {}", e),
+                Ok(CallToolResult {
+                    content: vec![Content::text(format!(
+                        "文件已成功发送: {}{}",
+                        file_name,
+                        Self::message_id_suffix(&delivery)
+                    ))],
+                    is_error: None,
+                    meta: None,
+                    structured_content: serde_json::to_value(&delivery).ok(),
+                })
+            }
+            Err(e) => Ok(self.tool_failure("发送文件失败", &e).await),
+        }
+    }
+
+    async fn handle_post_link(
+        &self,
+        params: serde_json::Value,
+    ) -> Result<CallToolResult, ErrorData> {
+        let title = params["title"]
+            .as_str()
+            .ok_or_else(|| ErrorData::invalid_params("title parameter is required", None))?;
+        let link_url = params["link_url"]
+            .as_str()
+            .ok_or_else(|| ErrorData::invalid_params("link_url parameter is required", None))?;
+        Self::ensure_http_url("link_url", link_url)?;
+        let thumb_url = params["thumb_url"].as_str().unwrap_or_default();
+        if !thumb_url.is_empty() {
+            Self::ensure_http_url("thumb_url", thumb_url)?;
+        }
+
+        let link = LinkMessage {
+            title: title.to_string(),
+            desc: params["desc"].as_str().unwrap_or_default().to_string(),
+            link_url: link_url.to_string(),
+            thumb_url: thumb_url.to_string(),
+        };
+
+        self.emit_log_message(
+            LoggingLevel::Info,
+            format!("收到发送链接请求: {} ({})", title, link_url),
+        )
+        .await;
+
+        let mut recipient = self.api_client.config().default_recipient();
+        let quiet = self.media_quiet_hours();
+        match &quiet {
+            Some(quiet) if quiet.mode == QuietMode::Digest => {
+                let notice = format!("🔗 {}: {}", title, link_url);
+                return self.defer_text(&[recipient], None, &notice, quiet).await;
+            }
+            // 静默模式照常发送卡片，但不追加 @ 提醒
+            Some(_) => recipient.at_list = None,
+            None => {}
+        }
+
+        match self.api_client.post_link_to(&recipient, &link).await {
+            Ok(delivery) => {
+                self.emit_log_message(LoggingLevel::Info, format!("链接发送成功: {}", title))
+                    .await;
+                let mut text = format!(
+                    "链接已成功发送: {}{}",
+                    title,
+                    Self::message_id_suffix(&delivery)
+                );
+                if let Some(warning) = &delivery.warning {
+                    self.emit_log_message(
+                        LoggingLevel::Warning,
+                        format!("链接 {} 已发送，{}", title, warning),
+                    )
+                    .await;
+                    text.push_str(&format!("\n⚠️ {}，卡片已送达，请勿重试", warning));
+                }
+                let mut structured = serde_json::to_value(&delivery).ok();
+                if let Some(quiet) = &quiet {
+                    text.push_str("\n免打扰时段内，已取消 @ 提醒");
+                    if let Some(Value::Object(map)) = &mut structured {
+                        map.insert(
+                            "quiet_hours".to_string(),
+                            serde_json::to_value(quiet.mode).unwrap_or_default(),
+                        );
+                    }
+                }
+                Ok(CallToolResult {
+                    content: vec![Content::text(text)],
+                    is_error: None,
+                    meta: None,
+                    structured_content: structured,
+                })
+            }
+            Err(e) => Ok(self.tool_failure("发送链接失败", &e).await),
+        }
+    }
+
+    async fn handle_revoke_message(
+        &self,
+        params: serde_json::Value,
+    ) -> Result<CallToolResult, ErrorData> {
+        let new_msg_id = match &params["new_msg_id"] {
+            Value::Null => None,
+            Value::String(id) => Some(id.trim().to_string()),
+            Value::Number(id) => Some(id.to_string()),
+            _ => {
+                return Err(ErrorData::invalid_params(
+                    "new_msg_id must be a string",
+                    None,
+                ))
+            }
+        };
+
+        self.emit_log_message(
+            LoggingLevel::Info,
+            format!(
+                "收到撤回消息请求: {}",
+                new_msg_id.as_deref().unwrap_or("最近一条")
+            ),
+        )
+        .await;
+
+        match self.api_client.revoke_message(new_msg_id.as_deref()).await {
+            Ok(message) => {
+                self.emit_log_message(
+                    LoggingLevel::Info,
+                    format!("消息已撤回: {}", message.new_msg_id),
+                )
+                .await;
+                Ok(CallToolResult {
+                    content: vec![Content::text(format!(
+                        "已撤回发送给 {} 的消息 (newMsgId: {}): {}",
+                        message.to_wxid, message.new_msg_id, message.preview
+                    ))],
+                    is_error: None,
+                    meta: None,
+                    structured_content: None,
+                })
+            }
+            Err(e) => Ok(self.tool_failure("撤回消息失败", &e).await),
+        }
+    }
+
+    async fn handle_list_contacts(
+        &self,
+        params: serde_json::Value,
+    ) -> Result<CallToolResult, ErrorData> {
+        let kind = match &params["kind"] {
+            Value::Null => None,
+            Value::String(kind) => Some(
+                kind.parse::<ContactKind>()
+                    .map_err(|e| ErrorData::invalid_params(e, None))?,
+            ),
+            _ => return Err(ErrorData::invalid_params("kind must be a string", None)),
+        };
+        let query = match &params["query"] {
+            Value::Null => None,
+            Value::String(query) => Some(query.clone()),
+            _ => return Err(ErrorData::invalid_params("query must be a string", None)),
+        };
+        let limit = match &params["limit"] {
+            Value::Null => DEFAULT_CONTACT_LIMIT,
+            value => value.as_u64().filter(|limit| *limit > 0).ok_or_else(|| {
+                ErrorData::invalid_params("limit must be a positive integer", None)
+            })? as usize,
+        };
+        let filter = ContactFilter { kind, query };
+
+        let contacts = match self.api_client.list_contacts().await {
+            Ok(contacts) => filter.apply(contacts),
+            Err(e) => return Ok(self.tool_failure("获取通讯录失败", &e).await),
+        };
+        let total = contacts.len();
+        let shown = &contacts[..total.min(limit)];
+
+        let mut text = if total == 0 {
+            "没有匹配的联系人".to_string()
+        } else {
+            format!("共 {} 个匹配的联系人", total)
+        };
+        if shown.len() < total {
+            text.push_str(&format!("，仅列出前 {} 个", shown.len()));
+        }
+        for contact in shown {
+            text.push_str(&format!(
+                "\n- [{}] {}",
+                contact.kind.label(),
+                contact.nickname.as_deref().unwrap_or("(无昵称)")
+            ));
+            if let Some(remark) = &contact.remark {
+                text.push_str(&format!(" (备注: {})", remark));
+            }
+            text.push_str(&format!(": {}", contact.wxid));
+        }
+
+        Ok(CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: None,
+            meta: None,
+            structured_content: Some(serde_json::json!({
+                "total": total,
+                "contacts": shown,
+            })),
+        })
+    }
+
+    /// 将发送失败转换为 `is_error` 的工具结果，附带稳定的错误码，
+    /// 以便 Agent 区分可稍后重试的临时故障与需要人工处理的配置问题。
+    /// 错误详情放在 `_meta.error` 中：`structuredContent` 必须符合工具声明的 `outputSchema`。
+    async fn tool_failure(&self, action: &str, err: &GeweNoticeError) -> CallToolResult {
+        let level = if err.is_fatal() {
+            LoggingLevel::Error
+        } else {
+            LoggingLevel::Warning
+        };
+        self.emit_log_message(level, format!("{}: {}", action, err))
+            .await;
+
+        let mut error = serde_json::json!({
+            "code": err.code(),
+            "message": err.to_string(),
+            "retryable": err.is_retryable(),
+            "fatal": err.is_fatal(),
+            "api_code": err.api_code(),
+            "retry_after_ms": err.retry_after().map(|delay| delay.as_millis() as u64),
+        });
+        if let GeweNoticeError::PartialDelivery {
+            part,
+            total,
+            source,
+        } = err
+        {
+            error["failed_part"] = serde_json::json!(part);
+            error["total_parts"] = serde_json::json!(total);
+            error["cause"] = serde_json::json!(source.code());
+        }
+
+        CallToolResult {
+            content: vec![Content::text(format!(
+                "{}: {} [code: {}]",
+                action,
+                err,
+                err.code()
+            ))],
+            is_error: Some(true),
+            meta: Some(Meta(JsonObject::from_iter([("error".to_string(), error)]))),
+            structured_content: None,
+        }
+    }
+
+    fn message_id_suffix(delivery: &Delivery) -> String {
+        if delivery.messages.is_empty() {
+            return String::new();
+        }
+
+        let ids: Vec<&str> = delivery
+            .messages
+            .iter()
+            .map(|message| message.new_msg_id.as_str())
+            .collect();
+        format!(" (newMsgId: {})", ids.join(", "))
+    }
+
+    fn resolve_recipient(&self, params: &serde_json::Value) -> Result<Recipient, ErrorData> {
+        let config = self.api_client.config();
+        let mut recipient = match (&params["recipient"], &params["to"]) {
+            (Value::Null, Value::Null) => config.default_recipient(),
+            (Value::String(alias), Value::Null) => {
+                config.recipient_by_alias(alias.trim()).ok_or_else(|| {
+                    let aliases = config.recipient_aliases();
+                    ErrorData::invalid_params(
+                        if aliases.is_empty() {
+                            format!(
+                                "unknown recipient '{}': no recipients are configured",
+                                alias
+                            )
+                        } else {
+                            format!(
+                                "unknown recipient '{}', expected one of: {}",
+                                alias,
+                                aliases.join(", ")
+                            )
+                        },
+                        None,
+                    )
+                })?
+            }
+            (Value::Null, Value::String(to)) => {
+                let wxid = WxId::new(to.trim().to_string()).map_err(|e| {
+                    ErrorData::invalid_params(format!("to is invalid: {}", e), None)
+                })?;
+
+                config.recipient_override(&wxid).ok_or_else(|| {
+                    ErrorData::invalid_params(
+                        format!(
+                            "recipient {} is not in GEWE_NOTICE_ALLOWED_RECIPIENTS",
+                            wxid
+                        ),
+                        None,
+                    )
+                })?
+            }
+            (Value::Null, _) => return Err(ErrorData::invalid_params("to must be a string", None)),
+            (Value::String(_), _) => {
+                return Err(ErrorData::invalid_params(
+                    "recipient and to cannot be used together",
+                    None,
+                ))
+            }
+            _ => {
+                return Err(ErrorData::invalid_params(
+                    "recipient must be a string",
                     None,
                 ))
             }
+        };
+
+        if let Some(at_list) = Self::parse_at_override(params)? {
+            recipient.at_list = (!at_list.is_empty()).then_some(at_list);
+        }
+
+        Ok(recipient)
+    }
+
+    fn parse_at_override(params: &serde_json::Value) -> Result<Option<Vec<WxId>>, ErrorData> {
+        let entries = match &params["at"] {
+            Value::Null => return Ok(None),
+            Value::Array(entries) => entries,
+            _ => return Err(ErrorData::invalid_params("at must be an array", None)),
+        };
+
+        let raw = entries
+            .iter()
+            .map(|entry| {
+                entry
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| ErrorData::invalid_params("at entries must be strings", None))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Config::parse_wxid_list(raw)
+            .map(Some)
+            .map_err(|e| ErrorData::invalid_params(format!("at is invalid: {}", e), None))
+    }
+
+    fn ensure_http_url(name: &str, value: &str) -> Result<(), ErrorData> {
+        match reqwest::Url::parse(value) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+            _ => Err(ErrorData::invalid_params(
+                format!("{} must be an http(s) URL", name),
+                None,
+            )),
+        }
+    }
+
+    fn object_schema(properties: Value, required: &[&str]) -> Arc<JsonObject> {
+        let mut schema = serde_json::Map::new();
+        schema.insert("type".to_string(), serde_json::json!("object"));
+        schema.insert("properties".to_string(), properties);
+        schema.insert("required".to_string(), serde_json::json!(required));
+        Arc::new(schema)
+    }
+
+    fn post_text_tool(aliases: &[String]) -> Tool {
+        Tool {
+            name: "post_text".into(),
+            title: Some("发送通知".into()),
+            description: Some(
+                "发送 AI 任务状态通知。Agent 应在任务完成或发生关键错误时调用此工具。".into(),
+            ),
+            input_schema: Self::object_schema(
+                Self::with_recipient_properties(
+                    serde_json::json!({
+                        "content": {
+                            "type": "string",
+                            "description": "要发送的通知文本内容"
+                        },
+                        "severity": {
+                            "type": "string",
+                            "enum": Severity::ALL.map(Severity::as_str),
+                            "description": "严重程度，用于路由规则匹配，默认 info"
+                        }
+                    }),
+                    aliases,
+                ),
+                &["content"],
+            ),
+            output_schema: Some(Self::delivery_output_schema()),
+            annotations: None,
+            icons: None,
+        }
+    }
+
+    fn notify_status_tool(aliases: &[String]) -> Tool {
+        Tool {
+            name: "notify_status".into(),
+            title: Some("发送结构化状态通知".into()),
+            description: Some(
+                "按统一排版发送任务状态通知，无需手动拼接表情和格式。适合汇报任务成功、失败、警告或进度。"
+                    .into(),
+            ),
+            input_schema: Self::object_schema(
+                Self::with_recipient_properties(serde_json::json!({
+                    "status": {
+                        "type": "string",
+                        "enum": Status::ALL.map(Status::as_str),
+                        "description": "任务状态"
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "模块或任务名称，例如 Build、Deploy"
+                    },
+                    "summary": {
+                        "type": "string",
+                        "description": "一句话概述 (可选)"
+                    },
+                    "fields": {
+                        "type": "object",
+                        "additionalProperties": { "type": ["string", "number", "boolean"] },
+                        "description": "附加的键值信息，例如 {\"耗时\": \"3m\", \"失败用例\": 2}，按传入顺序展示"
+                    },
+                    "severity": {
+                        "type": "string",
+                        "enum": Severity::ALL.map(Severity::as_str),
+                        "description": "严重程度，用于路由规则匹配与免打扰判断，默认与 status 相同；critical 在免打扰时段内仍立即发送"
+                    }
+                }), aliases),
+                &["status", "title"],
+            ),
+            output_schema: Some(Self::delivery_output_schema()),
+            annotations: None,
+            icons: None,
+        }
+    }
+
+    fn revoke_message_tool() -> Tool {
+        Tool {
+            name: "revoke_message".into(),
+            title: Some("撤回消息".into()),
+            description: Some(
+                format!(
+                    "撤回本服务最近发送的一条消息，例如误发的\"部署成功\"。仅能撤回本进程发送的最近 {} 条消息，\
+                     且微信只允许撤回两分钟内的消息。",
+                    SENT_HISTORY_CAPACITY
+                )
+                .into(),
+            ),
+            input_schema: Self::object_schema(
+                serde_json::json!({
+                    "new_msg_id": {
+                        "type": "string",
+                        "description": "要撤回消息的 newMsgId (见发送工具的返回结果)；省略时撤回最近发送的一条"
+                    }
+                }),
+                &[],
+            ),
+            output_schema: None,
+            annotations: Some(ToolAnnotations {
+                destructive_hint: Some(true),
+                ..Default::default()
+            }),
+            icons: None,
+        }
+    }
+
+    fn list_contacts_tool() -> Tool {
+        Tool {
+            name: "list_contacts".into(),
+            title: Some("查询通讯录".into()),
+            description: Some(
+                "列出机器人账号的好友与群聊及其昵称、备注和 wxid，用于查找发送目标的 wxid。只读，不会发送消息。"
+                    .into(),
+            ),
+            input_schema: Self::object_schema(
+                serde_json::json!({
+                    "kind": {
+                        "type": "string",
+                        "enum": ["friend", "chatroom"],
+                        "description": "可选，只列出好友或群聊"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "可选，在 wxid、昵称与备注中查找的关键字，不区分大小写"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": format!("最多返回的联系人数量，默认 {}", DEFAULT_CONTACT_LIMIT)
+                    }
+                }),
+                &[],
+            ),
+            output_schema: Some(Self::object_schema(
+                serde_json::json!({
+                    "total": { "type": "integer", "description": "匹配的联系人总数" },
+                    "contacts": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "wxid": { "type": "string" },
+                                "kind": { "type": "string", "enum": ["friend", "chatroom"] },
+                                "nickname": { "type": ["string", "null"], "description": "昵称；群聊为群名称" },
+                                "remark": { "type": ["string", "null"], "description": "备注" }
+                            },
+                            "required": ["wxid", "kind"]
+                        }
+                    }
+                }),
+                &["total", "contacts"],
+            )),
+            annotations: Some(ToolAnnotations {
+                read_only_hint: Some(true),
+                ..Default::default()
+            }),
+            icons: None,
+        }
+    }
+
+    fn delivery_output_schema() -> Arc<JsonObject> {
+        let properties = serde_json::json!({
+            "recipient": { "type": "string", "description": "实际接收者 WXID" },
+            "parts": { "type": "integer", "description": "发送的分段数量" },
+            "messages": {
+                "type": "array",
+                "description": "Gewe 返回的消息标识，可用于 revoke_message",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "kind": { "type": "string", "enum": ["text", "image", "file", "link"] },
+                        "to_wxid": { "type": "string" },
+                        "preview": { "type": "string" },
+                        "msg_id": { "type": "string" },
+                        "new_msg_id": { "type": "string" },
+                        "create_time": { "type": "string" }
+                    },
+                    "required": ["kind", "to_wxid", "new_msg_id"]
+                }
+            },
+            "contents": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "每条消息最终发出的内容，包含 @ 前缀与分段序号"
+            },
+            "mentioned": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "实际被 @ 的 WXID，@ 全体成员时为 [\"all\"]"
+            },
+            "at_all_fallback": {
+                "type": "boolean",
+                "description": "@ 全体成员因无权限降级为普通消息"
+            },
+            "attempts": { "type": "integer", "description": "发送请求的尝试次数，包含重试" },
+            "elapsed_ms": { "type": "integer", "description": "发送耗时 (毫秒)" },
+            "endpoint": { "type": "string", "description": "处理本次发送的 Gewe 地址，配置多个地址时用于判断是否发生了故障切换" },
+            "warning": {
+                "type": "string",
+                "description": "消息已送达但附带操作失败时的说明，例如链接卡片后的 @ 提醒未能发送"
+            },
+            "deferred": {
+                "type": "boolean",
+                "description": "通知因免打扰时段被暂存、尚未发送"
+            },
+            "deliver_after": {
+                "type": ["string", "null"],
+                "description": "暂存通知预计汇总发送的时间 (RFC 3339)"
+            },
+            "quiet_hours": {
+                "type": "string",
+                "enum": ["digest", "silent"],
+                "description": "免打扰时段生效时的处理方式: digest 暂存汇总，silent 发送但不 @"
+            },
+            "routing": {
+                "type": "object",
+                "description": "路由决策 (仅 post_text / notify_status)",
+                "properties": {
+                    "reason": { "type": "string", "enum": ["explicit", "rule", "default"] },
+                    "rule": { "type": ["string", "null"], "description": "命中的路由规则名称" },
+                    "severity": { "type": "string" },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "source": { "type": ["string", "null"] },
+                    "recipients": { "type": "array", "items": { "type": "string" } }
+                }
+            },
+            "deliveries": {
+                "type": "array",
+                "items": { "type": "object" },
+                "description": "路由到多个接收者时，每个接收者的投递信息；顶层字段对应第一个接收者"
+            }
+        });
+
+        Self::object_schema(
+            properties,
+            &[
+                "recipient",
+                "parts",
+                "messages",
+                "contents",
+                "mentioned",
+                "at_all_fallback",
+                "attempts",
+                "elapsed_ms",
+                "endpoint",
+            ],
+        )
+    }
+
+    fn with_recipient_properties(mut properties: Value, aliases: &[String]) -> Value {
+        if let Some(map) = properties.as_object_mut() {
+            if !aliases.is_empty() {
+                map.insert(
+                    "recipient".to_string(),
+                    serde_json::json!({
+                        "type": "string",
+                        "enum": aliases,
+                        "description": "可选的具名接收者别名（在配置文件 [recipients] 中定义），不能与 to 同时使用"
+                    }),
+                );
+            }
+            map.insert(
+                "to".to_string(),
+                serde_json::json!({
+                    "type": "string",
+                    "description": "可选的接收者 WXID，需在允许列表中；省略时发送给默认接收者"
+                }),
+            );
+            map.insert(
+                "at".to_string(),
+                serde_json::json!({
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "可选，本次群聊消息要 @ 的 WXID 列表或 [\"all\"]，覆盖默认 @ 列表；传空数组则不 @ 任何人"
+                }),
+            );
+            map.insert(
+                "tags".to_string(),
+                serde_json::json!({
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "可选的标签，例如 [\"deploy\"]，用于路由规则匹配"
+                }),
+            );
+            map.insert(
+                "source".to_string(),
+                serde_json::json!({
+                    "type": "string",
+                    "description": "可选的消息来源，例如 Agent 或流水线名称，用于路由规则匹配"
+                }),
+            );
+        }
+        properties
+    }
+
+    fn post_image_tool() -> Tool {
+        Tool {
+            name: "post_image".into(),
+            title: Some("发送图片".into()),
+            description: Some(
+                "发送图片消息，例如 UI 测试截图或基准测试图表。图片需可通过 http(s) URL 访问。\
+                 免打扰时段按非紧急通知处理。"
+                    .into(),
+            ),
+            input_schema: Self::object_schema(
+                serde_json::json!({
+                    "image_url": {
+                        "type": "string",
+                        "description": "图片的 http(s) 下载地址"
+                    }
+                }),
+                &["image_url"],
+            ),
+            output_schema: Some(Self::delivery_output_schema()),
+            annotations: None,
+            icons: None,
+        }
+    }
+
+    fn post_file_tool() -> Tool {
+        Tool {
+            name: "post_file".into(),
+            title: Some("发送文件".into()),
+            description: Some(
+                format!(
+                    "以附件形式发送构建日志、测试报告等文件。文件需可通过 http(s) URL 访问，\
+                     大小不超过 {} MiB，支持的扩展名: {}。免打扰时段按非紧急通知处理。",
+                    MAX_FILE_SIZE / 1024 / 1024,
+                    ALLOWED_FILE_EXTENSIONS.join(", ")
+                )
+                .into(),
+            ),
+            input_schema: Self::object_schema(
+                serde_json::json!({
+                    "file_name": {
+                        "type": "string",
+                        "description": "在微信中显示的文件名，需包含扩展名，例如 test-report.html"
+                    },
+                    "file_url": {
+                        "type": "string",
+                        "description": "文件的 http(s) 下载地址"
+                    }
+                }),
+                &["file_name", "file_url"],
+            ),
+            output_schema: Some(Self::delivery_output_schema()),
+            annotations: None,
+            icons: None,
+        }
+    }
+
+    fn post_link_tool() -> Tool {
+        Tool {
+            name: "post_link".into(),
+            title: Some("发送链接卡片".into()),
+            description: Some(
+                "以微信链接卡片形式发送 PR、仪表盘等链接。群聊中配置了 @ 列表时会追加一条 @ 提醒。\
+                 免打扰时段按非紧急通知处理。"
+                    .into(),
+            ),
+            input_schema: Self::object_schema(
+                serde_json::json!({
+                    "title": {
+                        "type": "string",
+                        "description": "卡片标题"
+                    },
+                    "desc": {
+                        "type": "string",
+                        "description": "卡片描述 (可选)"
+                    },
+                    "link_url": {
+                        "type": "string",
+                        "description": "点击卡片后打开的 http(s) 地址"
+                    },
+                    "thumb_url": {
+                        "type": "string",
+                        "description": "卡片缩略图的 http(s) 地址 (可选)"
+                    }
+                }),
+                &["title", "link_url"],
+            ),
+            output_schema: Some(Self::delivery_output_schema()),
+            annotations: None,
+            icons: None,
         }
     }
 }
@@ -253,7 +1539,9 @@ impl ServerHandler for GeweNoticeServer {
         InitializeResult {
             protocol_version: Default::default(),
             capabilities: ServerCapabilities {
-                tools: Some(ToolsCapability { list_changed: None }),
+                tools: Some(ToolsCapability {
+                    list_changed: Some(true),
+                }),
                 prompts: None,
                 resources: None,
                 completions: None,
@@ -276,40 +1564,16 @@ impl ServerHandler for GeweNoticeServer {
         _request: Option<PaginatedRequestParam>,
         _context: rmcp::service::RequestContext<rmcp::service::RoleServer>,
     ) -> Result<ListToolsResult, ErrorData> {
-        let tools = vec![Tool {
-            name: "post_text".into(),
-            title: Some("发送通知".into()),
-            description: Some(
-                "发送 AI 任务状态通知。Agent 应在任务完成或发生关键错误时调用此工具。".into(),
-            ),
-            input_schema: {
-                let mut schema = serde_json::Map::new();
-                schema.insert("type".to_string(), serde_json::json!("object"));
-
-                let mut properties = serde_json::Map::new();
-                let mut content_prop = serde_json::Map::new();
-                content_prop.insert("type".to_string(), serde_json::json!("string"));
-                content_prop.insert(
-                    "description".to_string(),
-                    serde_json::json!("要发送的通知文本内容"),
-                );
-                properties.insert(
-                    "content".to_string(),
-                    serde_json::Value::Object(content_prop),
-                );
-
-                schema.insert(
-                    "properties".to_string(),
-                    serde_json::Value::Object(properties),
-                );
-                schema.insert("required".to_string(), serde_json::json!(["content"]));
-
-                Arc::new(schema)
-            },
-            output_schema: None,
-            annotations: None,
-            icons: None,
-        }];
+        let aliases = self.api_client.config().recipient_aliases();
+        let tools = vec![
+            Self::post_text_tool(&aliases),
+            Self::notify_status_tool(&aliases),
+            Self::post_image_tool(),
+            Self::post_file_tool(),
+            Self::post_link_tool(),
+            Self::revoke_message_tool(),
+            Self::list_contacts_tool(),
+        ];
 
         Ok(ListToolsResult {
             tools,
@@ -328,6 +1592,36 @@ impl ServerHandler for GeweNoticeServer {
                 self.handle_post_text(serde_json::Value::Object(arguments))
                     .await
             }
+            "notify_status" => {
+                let arguments = request.arguments.unwrap_or_default();
+                self.handle_notify_status(serde_json::Value::Object(arguments))
+                    .await
+            }
+            "post_image" => {
+                let arguments = request.arguments.unwrap_or_default();
+                self.handle_post_image(serde_json::Value::Object(arguments))
+                    .await
+            }
+            "post_file" => {
+                let arguments = request.arguments.unwrap_or_default();
+                self.handle_post_file(serde_json::Value::Object(arguments))
+                    .await
+            }
+            "post_link" => {
+                let arguments = request.arguments.unwrap_or_default();
+                self.handle_post_link(serde_json::Value::Object(arguments))
+                    .await
+            }
+            "revoke_message" => {
+                let arguments = request.arguments.unwrap_or_default();
+                self.handle_revoke_message(serde_json::Value::Object(arguments))
+                    .await
+            }
+            "list_contacts" => {
+                let arguments = request.arguments.unwrap_or_default();
+                self.handle_list_contacts(serde_json::Value::Object(arguments))
+                    .await
+            }
             _ => Err(ErrorData::method_not_found::<CallToolRequestMethod>()),
         }
     }
@@ -336,19 +1630,29 @@ impl ServerHandler for GeweNoticeServer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::Config;
     use crate::gewe_api::GeweApiClient;
     use serde_json::Value;
+    use std::time::Duration;
 
     fn test_config() -> Config {
         use crate::config::{AppId, ValidatedToken, WxId};
 
         Config {
-            base_url: "http://example.com".into(),
+            base_urls: vec!["http://example.com".into()],
             token: ValidatedToken::new("00000000-0000-0000-0000-000000000000").expect("valid UUID"),
+            token_source: Default::default(),
             app_id: AppId::new("wx_test".to_string()).expect("valid app_id"),
             wxid: WxId::new("wxid_test".to_string()).expect("valid wxid"),
             at_list: None,
+            allowed_recipients: vec![WxId::new("ops@chatroom".to_string()).expect("valid wxid")],
+            max_message_length: crate::config::DEFAULT_MAX_MESSAGE_LENGTH,
+            http: Default::default(),
+            transport: Default::default(),
+            quiet_hours: None,
+            exec: Default::default(),
+            recipients: Default::default(),
+            routes: Vec::new(),
+            config_file: None,
         }
     }
 
@@ -391,4 +1695,577 @@ mod tests {
             GeweNoticeServer::test_level_value(LoggingLevel::Warning)
         );
     }
+
+    #[tokio::test]
+    async fn post_text_rejects_recipient_outside_allowlist() {
+        let client = GeweApiClient::new(test_config()).expect("client");
+        let server = GeweNoticeServer::new(client);
+
+        let err = server
+            .handle_post_text(serde_json::json!({
+                "content": "hello",
+                "to": "wxid_stranger"
+            }))
+            .await
+            .expect_err("recipient should be rejected");
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+
+        let err = server
+            .handle_post_text(serde_json::json!({
+                "content": "hello",
+                "to": "bad@chatroomx"
+            }))
+            .await
+            .expect_err("malformed wxid should be rejected");
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn resolve_recipient_accepts_allowlisted_target() {
+        let client = GeweApiClient::new(test_config()).expect("client");
+        let server = GeweNoticeServer::new(client);
+
+        let recipient = server
+            .resolve_recipient(&serde_json::json!({ "to": "ops@chatroom" }))
+            .expect("allowlisted");
+        assert_eq!(recipient.wxid.as_str(), "ops@chatroom");
+
+        let recipient = server
+            .resolve_recipient(&serde_json::json!({}))
+            .expect("default");
+        assert_eq!(recipient.wxid.as_str(), "wxid_test");
+    }
+
+    #[test]
+    fn resolve_recipient_applies_at_override() {
+        let mut config = test_config();
+        config.wxid = WxId::new("team@chatroom".to_string()).expect("valid wxid");
+        config.at_list = Some(vec![WxId::new("all".to_string()).expect("valid wxid")]);
+        let server = GeweNoticeServer::new(GeweApiClient::new(config).expect("client"));
+
+        let recipient = server
+            .resolve_recipient(&serde_json::json!({ "at": [" wxid_a ", "wxid_b"] }))
+            .expect("valid at list");
+        assert_eq!(
+            recipient.normalized_at_list(),
+            Some(vec!["wxid_a".to_string(), "wxid_b".to_string()])
+        );
+
+        let recipient = server
+            .resolve_recipient(&serde_json::json!({ "at": [] }))
+            .expect("empty at list");
+        assert!(recipient.at_list.is_none());
+
+        let err = server
+            .resolve_recipient(&serde_json::json!({ "at": ["bad@chatroomx"] }))
+            .expect_err("invalid wxid");
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn notify_status_rejects_unknown_status() {
+        let server = GeweNoticeServer::new(GeweApiClient::new(test_config()).expect("client"));
+
+        let err = server
+            .handle_notify_status(serde_json::json!({
+                "status": "done",
+                "title": "Build"
+            }))
+            .await
+            .expect_err("unknown status");
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+
+        let err = server
+            .handle_notify_status(serde_json::json!({
+                "status": "success",
+                "title": "Build",
+                "fields": ["not", "an", "object"]
+            }))
+            .await
+            .expect_err("fields must be an object");
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn revoke_message_without_history_is_tool_error() {
+        let server = GeweNoticeServer::new(GeweApiClient::new(test_config()).expect("client"));
+
+        let result = server
+            .handle_revoke_message(serde_json::json!({}))
+            .await
+            .expect("failures are tool results");
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(
+            result.meta.expect("error meta").0["error"]["code"],
+            "unknown_message"
+        );
+    }
+
+    #[test]
+    fn send_tools_declare_delivery_output_schema() {
+        let send_tools = [
+            GeweNoticeServer::post_text_tool(&[]),
+            GeweNoticeServer::notify_status_tool(&[]),
+            GeweNoticeServer::post_image_tool(),
+            GeweNoticeServer::post_file_tool(),
+            GeweNoticeServer::post_link_tool(),
+        ];
+
+        for tool in send_tools {
+            let schema = tool
+                .output_schema
+                .expect("send tools declare output schema");
+            let properties = schema["properties"].as_object().expect("properties");
+            for key in [
+                "recipient",
+                "messages",
+                "mentioned",
+                "attempts",
+                "elapsed_ms",
+                "endpoint",
+            ] {
+                assert!(
+                    properties.contains_key(key),
+                    "{} missing {}",
+                    tool.name,
+                    key
+                );
+            }
+        }
+
+        assert!(GeweNoticeServer::revoke_message_tool()
+            .output_schema
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn list_contacts_is_read_only_and_validates_params() {
+        let tool = GeweNoticeServer::list_contacts_tool();
+        let annotations = tool.annotations.expect("annotations");
+        assert_eq!(annotations.read_only_hint, Some(true));
+
+        let server = GeweNoticeServer::new(GeweApiClient::new(test_config()).expect("client"));
+        for params in [
+            serde_json::json!({ "kind": "channel" }),
+            serde_json::json!({ "limit": 0 }),
+            serde_json::json!({ "query": 42 }),
+        ] {
+            let err = server
+                .handle_list_contacts(params.clone())
+                .await
+                .expect_err("invalid params");
+            assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS, "{params}");
+        }
+    }
+
+    #[tokio::test]
+    async fn send_failures_are_tool_errors_with_codes() {
+        let mut config = test_config();
+        config.base_urls = vec!["http://127.0.0.1:1".into()];
+        let server = GeweNoticeServer::new(GeweApiClient::new(config).expect("client"));
+
+        let result = server
+            .handle_post_text(serde_json::json!({ "content": "hello" }))
+            .await
+            .expect("failures are tool results");
+        assert_eq!(result.is_error, Some(true));
+        assert!(
+            result.structured_content.is_none(),
+            "failures must not violate the declared output schema"
+        );
+        let error = &result.meta.expect("error meta").0["error"];
+        assert_eq!(error["code"], "network_connection_refused");
+        assert_eq!(error["retryable"], true);
+        assert_eq!(error["fatal"], false);
+
+        let result = server
+            .handle_post_file(serde_json::json!({
+                "file_name": "payload.exe",
+                "file_url": "https://example.com/payload.exe"
+            }))
+            .await
+            .expect("failures are tool results");
+        assert_eq!(result.is_error, Some(true));
+        let error = &result.meta.expect("error meta").0["error"];
+        assert_eq!(error["code"], "attachment_unsupported_extension");
+        assert_eq!(error["retryable"], false);
+    }
+
+    fn all_day_quiet(mode: crate::quiet_hours::QuietMode) -> QuietHours {
+        QuietHours {
+            windows: vec![
+                "00:00-12:00".parse().expect("window"),
+                "12:00-00:00".parse().expect("window"),
+            ],
+            timezone: Default::default(),
+            mode,
+        }
+    }
+
+    #[tokio::test]
+    async fn quiet_hours_defer_notices_until_flushed() {
+        let mut config = test_config();
+        config.base_urls = vec!["http://127.0.0.1:1".into()];
+        config.quiet_hours = Some(all_day_quiet(QuietMode::Digest));
+        let server = GeweNoticeServer::new(GeweApiClient::new(config.clone()).expect("client"));
+
+        let result = server
+            .handle_post_text(serde_json::json!({ "content": "build finished" }))
+            .await
+            .expect("deferred result");
+        assert_eq!(result.is_error, None);
+        let structured = result.structured_content.expect("structured content");
+        assert_eq!(structured["deferred"], true);
+        assert_eq!(structured["quiet_hours"], "digest");
+        assert_eq!(structured["parts"], 0);
+        assert_eq!(structured["deliver_after"], Value::Null);
+        assert_eq!(server.deferred.len(), 1);
+
+        // 仍在免打扰时段内，不发送
+        assert_eq!(server.flush_deferred().await, 0);
+        assert_eq!(server.deferred.len(), 1);
+
+        // 时段结束但发送失败时，通知放回队列
+        config.quiet_hours = None;
+        server
+            .api_client
+            .replace_config(config)
+            .expect("valid config");
+        assert_eq!(server.flush_deferred().await, 0);
+        assert_eq!(server.deferred.len(), 1);
+    }
+
+    /// 在本地启动模拟的 Gewe API，返回其地址。
+    async fn mock_gewe(routes: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind");
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, routes).await.ok() });
+        address
+    }
+
+    #[tokio::test]
+    async fn quiet_hours_defer_media_and_drop_undeliverable_digests() {
+        let routes = axum::Router::new().route(
+            "/gewe/v2/api/message/postText",
+            axum::routing::post(|| async {
+                axum::Json(serde_json::json!({
+                    "ret": 500, "msg": "操作失败", "data": {"code": "-219"}
+                }))
+            }),
+        );
+        let mut config = test_config();
+        config.base_urls = vec![mock_gewe(routes).await];
+        config.quiet_hours = Some(all_day_quiet(QuietMode::Digest));
+        let server = GeweNoticeServer::new(GeweApiClient::new(config.clone()).expect("client"));
+
+        let result = server
+            .handle_post_image(serde_json::json!({ "image_url": "https://example.com/a.png" }))
+            .await
+            .expect("deferred result");
+        assert_eq!(
+            result.structured_content.expect("structured")["deferred"],
+            true
+        );
+        let result = server
+            .handle_post_link(serde_json::json!({
+                "title": "PR #42",
+                "link_url": "https://example.com/pr/42"
+            }))
+            .await
+            .expect("deferred result");
+        assert_eq!(
+            result.structured_content.expect("structured")["deferred"],
+            true
+        );
+        let result = server
+            .handle_post_file(serde_json::json!({
+                "file_name": "payload.exe",
+                "file_url": "https://example.com/payload.exe"
+            }))
+            .await
+            .expect("failures are tool results");
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(server.deferred.len(), 2);
+
+        // 机器人不在群内，重试也无法送达：丢弃而不是每次检查都放回队列
+        config.quiet_hours = None;
+        server
+            .api_client
+            .replace_config(config)
+            .expect("valid config");
+        assert_eq!(server.flush_deferred().await, 0);
+        assert!(server.deferred.is_empty());
+    }
+
+    #[tokio::test]
+    async fn critical_notices_bypass_quiet_hours() {
+        let mut config = test_config();
+        config.base_urls = vec!["http://127.0.0.1:1".into()];
+        config.quiet_hours = Some(all_day_quiet(QuietMode::Digest));
+        let server = GeweNoticeServer::new(GeweApiClient::new(config).expect("client"));
+
+        let result = server
+            .handle_post_text(serde_json::json!({ "content": "disk full", "severity": "critical" }))
+            .await
+            .expect("failures are tool results");
+        // 发送被实际尝试，因此得到的是网络错误而不是暂存结果
+        assert_eq!(result.is_error, Some(true));
+        assert!(server.deferred.is_empty());
+
+        let result = server
+            .handle_notify_status(serde_json::json!({
+                "status": "failure",
+                "title": "Nightly ETL",
+                "severity": "critical"
+            }))
+            .await
+            .expect("failures are tool results");
+        assert_eq!(result.is_error, Some(true));
+        assert!(server.deferred.is_empty());
+
+        // 未指定 severity 时按 status 判断，failure 仍会被暂存
+        let result = server
+            .handle_notify_status(
+                serde_json::json!({ "status": "failure", "title": "Nightly ETL" }),
+            )
+            .await
+            .expect("deferred result");
+        assert_eq!(
+            result.structured_content.expect("structured")["deferred"],
+            true
+        );
+        assert_eq!(server.deferred.len(), 1);
+    }
+
+    fn config_with_profiles() -> Config {
+        let mut config = test_config();
+        config.recipients.insert(
+            "ops".into(),
+            Recipient {
+                wxid: WxId::new("ops@chatroom".into()).unwrap(),
+                at_list: Some(vec![WxId::new("wxid_oncall".into()).unwrap()]),
+            },
+        );
+        config.recipients.insert(
+            "me".into(),
+            Recipient {
+                wxid: WxId::new("wxid_me".into()).unwrap(),
+                at_list: None,
+            },
+        );
+        config
+    }
+
+    #[tokio::test]
+    async fn partial_fan_out_failures_list_delivered_recipients() {
+        let routes = axum::Router::new().route(
+            "/gewe/v2/api/message/postText",
+            axum::routing::post(|body: axum::Json<Value>| async move {
+                if body["toWxid"] == "wxid_me" {
+                    axum::Json(serde_json::json!({
+                        "ret": 200, "msg": "操作成功", "data": {"newMsgId": 7}
+                    }))
+                } else {
+                    axum::Json(serde_json::json!({
+                        "ret": 500, "msg": "操作失败", "data": {"code": "-219"}
+                    }))
+                }
+            }),
+        );
+        let mut config = config_with_profiles();
+        config.base_urls = vec![mock_gewe(routes).await];
+        config.recipients.insert(
+            "dev".into(),
+            Recipient {
+                wxid: WxId::new("wxid_dev".into()).unwrap(),
+                at_list: None,
+            },
+        );
+        config.routes.push(crate::routing::RouteRule {
+            name: "everyone".into(),
+            severity: Vec::new(),
+            tags: Vec::new(),
+            source: Vec::new(),
+            recipients: vec!["me".into(), "ops".into(), "dev".into()],
+            at_list: None,
+        });
+        let server = GeweNoticeServer::new(GeweApiClient::new(config).expect("client"));
+
+        let result = server
+            .handle_post_text(serde_json::json!({ "content": "deploy done", "at": [] }))
+            .await
+            .expect("failures are tool results");
+        assert_eq!(result.is_error, Some(true));
+        let error = &result.meta.expect("error meta").0["error"];
+        assert_eq!(error["code"], "not_in_group");
+        assert_eq!(error["partial"], true);
+        assert_eq!(error["failed_recipient"], "ops@chatroom");
+        assert_eq!(error["skipped"], serde_json::json!(["wxid_dev"]));
+        assert_eq!(error["deliveries"][0]["recipient"], "wxid_me");
+    }
+
+    #[tokio::test]
+    async fn partial_fan_out_is_not_retryable() {
+        let routes = axum::Router::new().route(
+            "/gewe/v2/api/message/postText",
+            axum::routing::post(|body: axum::Json<Value>| async move {
+                if body["toWxid"] != "wxid_me" {
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                }
+                axum::Json(serde_json::json!({
+                    "ret": 200, "msg": "操作成功", "data": {"newMsgId": 7}
+                }))
+            }),
+        );
+        let mut config = config_with_profiles();
+        config.base_urls = vec![mock_gewe(routes).await];
+        config.http.request_timeout = Duration::from_millis(200);
+        config.http.retry.max_attempts = 1;
+        config.routes.push(crate::routing::RouteRule {
+            name: "everyone".into(),
+            severity: Vec::new(),
+            tags: Vec::new(),
+            source: Vec::new(),
+            recipients: vec!["me".into(), "ops".into()],
+            at_list: None,
+        });
+        let server = GeweNoticeServer::new(GeweApiClient::new(config).expect("client"));
+
+        let result = server
+            .handle_post_text(serde_json::json!({ "content": "deploy done", "at": [] }))
+            .await
+            .expect("failures are tool results");
+        let error = &result.meta.expect("error meta").0["error"];
+        // 超时本身可以重试，但 wxid_me 已收到通知
+        assert_eq!(error["code"], "network_timeout");
+        assert_eq!(error["partial"], true);
+        assert_eq!(error["retryable"], false);
+        assert_eq!(error["retry_after_ms"], Value::Null);
+        assert_eq!(error["failed_recipient"], "ops@chatroom");
+    }
+
+    #[test]
+    fn resolve_recipient_by_alias() {
+        let server =
+            GeweNoticeServer::new(GeweApiClient::new(config_with_profiles()).expect("client"));
+
+        let ops = server
+            .resolve_recipient(&serde_json::json!({ "recipient": "ops" }))
+            .expect("known alias");
+        assert_eq!(ops.wxid.as_str(), "ops@chatroom");
+        assert_eq!(ops.normalized_at_list(), Some(vec!["wxid_oncall".into()]));
+
+        let quiet = server
+            .resolve_recipient(&serde_json::json!({ "recipient": "ops", "at": [] }))
+            .expect("at override applies to aliases");
+        assert!(quiet.at_list.is_none());
+
+        let err = server
+            .resolve_recipient(&serde_json::json!({ "recipient": "release" }))
+            .expect_err("unknown alias");
+        assert!(err.message.contains("me, ops"));
+
+        let err = server
+            .resolve_recipient(&serde_json::json!({ "recipient": "me", "to": "wxid_me" }))
+            .expect_err("mutually exclusive");
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn recipient_aliases_are_schema_enum() {
+        let aliases = config_with_profiles().recipient_aliases();
+        let tool = GeweNoticeServer::post_text_tool(&aliases);
+        assert_eq!(
+            tool.input_schema["properties"]["recipient"]["enum"],
+            serde_json::json!(["me", "ops"])
+        );
+
+        let tool = GeweNoticeServer::post_text_tool(&[]);
+        assert!(tool.input_schema["properties"].get("recipient").is_none());
+    }
+
+    #[test]
+    fn route_message_prefers_explicit_target_over_rules() {
+        let mut config = config_with_profiles();
+        config.routes.push(crate::routing::RouteRule {
+            name: "failures".into(),
+            severity: vec![Severity::Failure],
+            tags: Vec::new(),
+            source: Vec::new(),
+            recipients: vec!["ops".into(), "me".into()],
+            at_list: None,
+        });
+        let server = GeweNoticeServer::new(GeweApiClient::new(config).expect("client"));
+
+        let (recipients, decision) = server
+            .route_message(
+                &serde_json::json!({ "tags": ["deploy"], "source": "ci" }),
+                Severity::Failure,
+            )
+            .expect("routed");
+        assert_eq!(decision.rule.as_deref(), Some("failures"));
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(decision.source.as_deref(), Some("ci"));
+
+        let (recipients, decision) = server
+            .route_message(&serde_json::json!({ "recipient": "me" }), Severity::Failure)
+            .expect("explicit");
+        assert_eq!(decision.reason, crate::routing::RouteReason::Explicit);
+        assert_eq!(recipients[0].wxid.as_str(), "wxid_me");
+
+        let (_, decision) = server
+            .route_message(&serde_json::json!({}), Severity::Progress)
+            .expect("default");
+        assert_eq!(decision.reason, crate::routing::RouteReason::Default);
+        assert_eq!(decision.recipients, vec!["wxid_test"]);
+
+        assert!(server
+            .route_message(&serde_json::json!({ "tags": "deploy" }), Severity::Info)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn reload_config_swaps_valid_config_and_keeps_old_on_error() {
+        use clap::Parser;
+
+        let path = std::env::temp_dir().join(format!("gewe-notice-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "wxid = \"first@chatroom\"\n[recipients.ops]\nwxid = \"ops@chatroom\"\n",
+        )
+        .unwrap();
+        let raw = RawConfig::parse_from([
+            "gewe-notice-mcp",
+            "--config",
+            path.to_str().unwrap(),
+            "--token",
+            "00000000-0000-0000-0000-000000000000",
+            "--app-id",
+            "wx_test",
+        ]);
+
+        let (server, tap) =
+            GeweNoticeServer::with_log_tap(GeweApiClient::new(test_config()).expect("client"));
+
+        assert!(server.reload_config(&raw).await);
+        let config = server.api_client.config();
+        assert_eq!(config.wxid_str(), "first@chatroom");
+        assert_eq!(config.recipient_aliases(), vec!["ops"]);
+        assert!(tap.lock().await.iter().any(|log| log
+            .data
+            .as_str()
+            .unwrap_or_default()
+            .contains("刷新工具列表")));
+
+        std::fs::write(&path, "wxid = \"broken@chatroomx\"\n").unwrap();
+        assert!(!server.reload_config(&raw).await);
+        assert_eq!(server.api_client.config().wxid_str(), "first@chatroom");
+        let last = tap.lock().await.last().cloned().expect("warning logged");
+        assert_eq!(last.level, LoggingLevel::Warning);
+        assert!(last.data.as_str().unwrap_or_default().contains("wxid"));
+
+        std::fs::remove_file(&path).ok();
+    }
 }
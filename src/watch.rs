@@ -0,0 +1,501 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+
+use crate::config::{Config, RawConfig};
+use crate::errors::Result;
+use crate::gewe_api::GeweApiClient;
+
+/// 检查文件新内容与轮转的间隔。
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 等待匹配行之后的上下文的最长时间，超时后用已有的行发送。
+pub const AFTER_CONTEXT_WAIT: Duration = Duration::from_secs(3);
+
+/// 每次从文件读取的块大小。
+const READ_CHUNK: usize = 64 * 1024;
+
+/// 单次轮询最多读取的字节数，一次追加的大量内容分多次轮询读完。
+const MAX_READ_PER_POLL: u64 = 4 * 1024 * 1024;
+
+/// 单行的最大长度，没有换行符的超长内容按此长度切分为多行。
+pub(crate) const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// 单个文件读取位置的标识，用于识别日志轮转 (文件被移走后在原路径重新创建)。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileId {
+    #[cfg(unix)]
+    dev: u64,
+    #[cfg(unix)]
+    ino: u64,
+}
+
+impl FileId {
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            Self {
+                dev: metadata.dev(),
+                ino: metadata.ino(),
+            }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+}
+
+/// 以 `tail -F` 的方式跟随文件：从末尾开始读取，文件被截断时从头读取，
+/// 被轮转 (移走或删除后重新创建) 时读完旧文件的剩余内容再切换到新文件。
+#[derive(Debug)]
+pub struct FileTail {
+    path: PathBuf,
+    file: Option<(File, FileId)>,
+    position: u64,
+    /// 尚未遇到换行符的行尾。
+    partial: Vec<u8>,
+}
+
+impl FileTail {
+    /// 打开文件并定位到末尾；文件尚不存在时等待其出现，之后从头读取。
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let mut tail = Self {
+            path: path.into(),
+            file: None,
+            position: 0,
+            partial: Vec::new(),
+        };
+        if tail.reopen() {
+            if let Some((file, _)) = &tail.file {
+                tail.position = file.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+        tail
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn reopen(&mut self) -> bool {
+        let opened = File::open(&self.path).and_then(|file| {
+            let id = FileId::of(&file.metadata()?);
+            Ok((file, id))
+        });
+        match opened {
+            Ok(file) => {
+                self.file = Some(file);
+                self.position = 0;
+                self.partial.clear();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// 读取自上次调用以来新写入的完整行，每次最多读取 [`MAX_READ_PER_POLL`] 字节。
+    pub fn read_lines(&mut self) -> io::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        if self.file.is_none() && !self.reopen() {
+            return Ok(lines);
+        }
+
+        let current = std::fs::metadata(&self.path).ok();
+        let rotated = match (&self.file, &current) {
+            (Some((_, id)), Some(metadata)) => FileId::of(metadata) != *id,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+
+        if let Some((file, _)) = &mut self.file {
+            let len = file.metadata()?.len();
+            if len < self.position {
+                // 原地截断 (copytruncate)，从头读取
+                self.position = 0;
+                self.partial.clear();
+            }
+            file.seek(SeekFrom::Start(self.position))?;
+            let mut chunk = vec![0; READ_CHUNK];
+            let mut read = 0;
+            loop {
+                if read >= MAX_READ_PER_POLL {
+                    // 旧文件尚未读完，轮转留到下一次轮询处理
+                    return Ok(lines);
+                }
+                let n = file.read(&mut chunk)?;
+                if n == 0 {
+                    break;
+                }
+                read += n as u64;
+                self.position += n as u64;
+                Self::push(&mut self.partial, &chunk[..n], &mut lines);
+            }
+        }
+
+        if rotated {
+            if !self.partial.is_empty() {
+                let rest = std::mem::take(&mut self.partial);
+                lines.push(Self::decode(&rest));
+            }
+            self.file = None;
+            if current.is_some() && self.reopen() {
+                lines.extend(self.read_lines()?);
+            }
+        }
+        Ok(lines)
+    }
+
+    fn push(partial: &mut Vec<u8>, buf: &[u8], lines: &mut Vec<String>) {
+        partial.extend_from_slice(buf);
+        while let Some(pos) = partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = partial.drain(..=pos).collect();
+            lines.push(Self::decode(&line));
+        }
+        while partial.len() > MAX_LINE_LEN {
+            let line: Vec<u8> = partial.drain(..MAX_LINE_LEN).collect();
+            lines.push(Self::decode(&line));
+        }
+    }
+
+    fn decode(line: &[u8]) -> String {
+        String::from_utf8_lossy(line)
+            .trim_end_matches(['\r', '\n'])
+            .to_string()
+    }
+}
+
+/// 一次匹配的通知内容。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub pattern: String,
+    /// 匹配行及其上下文，`true` 表示该行匹配了此模式。
+    pub lines: Vec<(bool, String)>,
+    /// 上一次通知后因冷却而未单独通知的匹配次数。
+    pub suppressed: usize,
+}
+
+impl Alert {
+    pub fn render(&self, path: &Path, hostname: &str) -> String {
+        let mut text = format!(
+            "🔍 日志匹配 /{}/\n文件: {}\n主机: {}",
+            self.pattern,
+            path.display(),
+            hostname
+        );
+        if self.suppressed > 0 {
+            text.push_str(&format!(
+                "\n(冷却期间另有 {} 次匹配未单独通知)",
+                self.suppressed
+            ));
+        }
+        text.push('\n');
+        for (matched, line) in &self.lines {
+            text.push_str(if *matched { "\n» " } else { "\n  " });
+            text.push_str(line);
+        }
+        text
+    }
+}
+
+#[derive(Debug)]
+struct Pending {
+    alert: Alert,
+    after_remaining: usize,
+    deadline: Instant,
+}
+
+#[derive(Debug, Default)]
+struct PatternState {
+    last_alert: Option<Instant>,
+    suppressed: usize,
+    pending: Option<Pending>,
+}
+
+/// 按模式匹配日志行，附带上下文，并对每个模式分别应用冷却时间。
+#[derive(Debug)]
+pub struct Matcher {
+    patterns: Vec<Regex>,
+    states: Vec<PatternState>,
+    context: usize,
+    cooldown: Duration,
+    before: VecDeque<String>,
+}
+
+impl Matcher {
+    pub fn new(patterns: Vec<Regex>, context: usize, cooldown: Duration) -> Self {
+        Self {
+            states: patterns.iter().map(|_| PatternState::default()).collect(),
+            patterns,
+            context,
+            cooldown,
+            before: VecDeque::with_capacity(context),
+        }
+    }
+
+    /// 处理一行日志，返回上下文已收集完整的通知。
+    pub fn feed(&mut self, line: &str, now: Instant) -> Vec<Alert> {
+        let mut ready = Vec::new();
+        for (pattern, state) in self.patterns.iter().zip(&mut self.states) {
+            let matched = pattern.is_match(line);
+
+            if let Some(pending) = &mut state.pending {
+                pending.alert.lines.push((matched, line.to_string()));
+                pending.after_remaining = pending.after_remaining.saturating_sub(1);
+                if pending.after_remaining == 0 {
+                    ready.extend(state.pending.take().map(|pending| pending.alert));
+                }
+                continue;
+            }
+            if !matched {
+                continue;
+            }
+            let cooling = state
+                .last_alert
+                .is_some_and(|last| now.duration_since(last) < self.cooldown);
+            if cooling {
+                state.suppressed += 1;
+                continue;
+            }
+
+            state.last_alert = Some(now);
+            let mut lines: Vec<(bool, String)> = self
+                .before
+                .iter()
+                .map(|line| (pattern.is_match(line), line.clone()))
+                .collect();
+            lines.push((true, line.to_string()));
+            let alert = Alert {
+                pattern: pattern.as_str().to_string(),
+                lines,
+                suppressed: std::mem::take(&mut state.suppressed),
+            };
+            if self.context == 0 {
+                ready.push(alert);
+            } else {
+                state.pending = Some(Pending {
+                    alert,
+                    after_remaining: self.context,
+                    deadline: now + AFTER_CONTEXT_WAIT,
+                });
+            }
+        }
+
+        if self.context > 0 {
+            if self.before.len() == self.context {
+                self.before.pop_front();
+            }
+            self.before.push_back(line.to_string());
+        }
+        ready
+    }
+
+    /// 返回等待后续上下文超时的通知。
+    pub fn expire(&mut self, now: Instant) -> Vec<Alert> {
+        self.states
+            .iter_mut()
+            .filter(|state| {
+                state
+                    .pending
+                    .as_ref()
+                    .is_some_and(|pending| now >= pending.deadline)
+            })
+            .filter_map(|state| state.pending.take().map(|pending| pending.alert))
+            .collect()
+    }
+}
+
+/// `watch` 子命令的参数。
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    pub file: PathBuf,
+    pub patterns: Vec<Regex>,
+    pub context: usize,
+    pub cooldown: Duration,
+}
+
+/// `watch` 子命令：持续跟随文件，对每次匹配发送通知，直到进程被终止。
+pub async fn run(raw: RawConfig, options: WatchOptions) -> Result<()> {
+    run_with_ready(raw, options, || {}).await
+}
+
+/// 与 [`run`] 相同，文件已打开并定位到末尾后调用 `on_ready`；此后追加的内容都会被检查。
+pub async fn run_with_ready(
+    raw: RawConfig,
+    options: WatchOptions,
+    on_ready: impl FnOnce(),
+) -> Result<()> {
+    let client = GeweApiClient::new(Config::load(raw)?)?;
+    let hostname = gethostname::gethostname().to_string_lossy().into_owned();
+    let mut tail = FileTail::open(&options.file);
+    let mut matcher = Matcher::new(options.patterns, options.context, options.cooldown);
+    eprintln!("正在监视 {}", tail.path().display());
+    on_ready();
+
+    let mut ticker = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let lines = match tail.read_lines() {
+            Ok(lines) => lines,
+            Err(e) => {
+                eprintln!("读取 {} 失败，稍后重试: {}", tail.path().display(), e);
+                continue;
+            }
+        };
+
+        let now = Instant::now();
+        let mut alerts = Vec::new();
+        for line in &lines {
+            alerts.extend(matcher.feed(line, now));
+        }
+        alerts.extend(matcher.expire(now));
+
+        for alert in alerts {
+            let text = alert.render(tail.path(), &hostname);
+            match client.post_text(&text).await {
+                Ok(_) => eprintln!("已发送匹配 /{}/ 的通知", alert.pattern),
+                Err(e) => eprintln!(
+                    "发送匹配 /{}/ 的通知失败 [{}]: {}",
+                    alert.pattern,
+                    e.code(),
+                    e
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn matcher(patterns: &[&str], context: usize, cooldown: Duration) -> Matcher {
+        let patterns = patterns.iter().map(|p| Regex::new(p).unwrap()).collect();
+        Matcher::new(patterns, context, cooldown)
+    }
+
+    #[test]
+    fn alerts_include_context_lines() {
+        let mut matcher = matcher(&["panic"], 1, Duration::ZERO);
+        let now = Instant::now();
+        assert!(matcher.feed("starting", now).is_empty());
+        assert!(matcher.feed("thread panicked", now).is_empty());
+        let alerts = matcher.feed("exiting", now);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(
+            alerts[0].lines,
+            vec![
+                (false, "starting".to_string()),
+                (true, "thread panicked".to_string()),
+                (false, "exiting".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_after_context_is_sent_after_wait() {
+        let mut matcher = matcher(&["ERROR"], 3, Duration::ZERO);
+        let now = Instant::now();
+        assert!(matcher.feed("ERROR disk full", now).is_empty());
+        assert!(matcher.expire(now).is_empty());
+        let alerts = matcher.expire(now + AFTER_CONTEXT_WAIT);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].lines.len(), 1);
+    }
+
+    #[test]
+    fn cooldown_is_per_pattern_and_counts_suppressed() {
+        let mut matcher = matcher(&["crash", "oom"], 0, Duration::from_secs(60));
+        let start = Instant::now();
+        assert_eq!(matcher.feed("crash 1", start).len(), 1);
+        assert!(matcher
+            .feed("crash 2", start + Duration::from_secs(1))
+            .is_empty());
+        assert!(matcher
+            .feed("crash 3", start + Duration::from_secs(2))
+            .is_empty());
+        // 其他模式不受影响
+        assert_eq!(matcher.feed("oom", start + Duration::from_secs(3)).len(), 1);
+
+        let alerts = matcher.feed("crash 4", start + Duration::from_secs(61));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].suppressed, 2);
+        assert!(alerts[0]
+            .render(Path::new("/var/log/app.log"), "host")
+            .contains("另有 2 次匹配"));
+    }
+
+    #[test]
+    fn tail_follows_truncation_and_rotation() {
+        let dir = std::env::temp_dir().join(format!("gewe-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        std::fs::write(&path, "old line\n").unwrap();
+
+        let mut tail = FileTail::open(&path);
+        assert!(tail.read_lines().unwrap().is_empty(), "starts at end");
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        write!(file, "first\nsecond (partial").unwrap();
+        assert_eq!(tail.read_lines().unwrap(), vec!["first"]);
+        writeln!(file, ")").unwrap();
+        assert_eq!(tail.read_lines().unwrap(), vec!["second (partial)"]);
+
+        // 轮转：旧文件被移走，原路径创建新文件
+        writeln!(file, "last words").unwrap();
+        std::fs::rename(&path, dir.join("app.log.1")).unwrap();
+        std::fs::write(&path, "fresh\n").unwrap();
+        assert_eq!(tail.read_lines().unwrap(), vec!["last words", "fresh"]);
+
+        // 截断
+        std::fs::write(&path, "").unwrap();
+        assert!(tail.read_lines().unwrap().is_empty());
+        std::fs::write(&path, "after truncate\n").unwrap();
+        assert_eq!(tail.read_lines().unwrap(), vec!["after truncate"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tail_reads_large_appends_in_bounded_polls() {
+        let dir = std::env::temp_dir().join(format!("gewe-watch-big-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        std::fs::write(&path, "").unwrap();
+        let mut tail = FileTail::open(&path);
+
+        let line = "x".repeat(1023);
+        let count = (MAX_READ_PER_POLL as usize / 1024) * 2;
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        for _ in 0..count {
+            writeln!(file, "{line}").unwrap();
+        }
+        // 没有换行符的超长内容被切分
+        write!(file, "{}", "y".repeat(MAX_LINE_LEN + 1)).unwrap();
+
+        let first = tail.read_lines().unwrap();
+        assert!(first.len() <= MAX_READ_PER_POLL as usize / 1024);
+        let mut total = first.len();
+        let mut rest = Vec::new();
+        while total <= count {
+            rest = tail.read_lines().unwrap();
+            assert!(!rest.is_empty());
+            total += rest.len();
+        }
+        assert_eq!(total, count + 1);
+        assert_eq!(rest.last().map(String::len), Some(MAX_LINE_LEN));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,19 @@
 use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 use once_cell::sync::Lazy;
 use reqwest::StatusCode;
 use serde_json::json;
 use tokio::sync::Mutex;
 
-use gewe_notice_mcp::config::{AppId, Config, ValidatedToken, WxId};
-use gewe_notice_mcp::errors::{ApiBusinessError, ApiErrorCode, GeweNoticeError};
-use gewe_notice_mcp::gewe_api::GeweApiClient;
+use gewe_notice_mcp::config::{
+    AppId, Config, Recipient, TokenSource, ValidatedToken, WxId, DEFAULT_MAX_MESSAGE_LENGTH,
+};
+use gewe_notice_mcp::errors::{
+    ApiBusinessError, ApiErrorCode, AttachmentError, GeweNoticeError, NetworkError,
+};
+use gewe_notice_mcp::gewe_api::{GeweApiClient, LinkMessage};
 
 static INIT_TRACING: Lazy<()> = Lazy::new(|| {
     let _ = tracing_subscriber::fmt::try_init();
@@ -56,11 +61,21 @@ fn base_config(base_url: String, at_list: Option<Vec<String>>) -> Config {
     });
 
     Config {
-        base_url,
+        base_urls: vec![base_url],
         token: ValidatedToken::new("00000000-0000-0000-0000-000000000000").expect("valid UUID"),
+        token_source: Default::default(),
         app_id: AppId::new("wx_test_app".to_string()).expect("valid app_id"),
         wxid: WxId::new("wxid_target@chatroom".to_string()).expect("valid wxid"),
         at_list: at_list_wxids,
+        allowed_recipients: Vec::new(),
+        max_message_length: DEFAULT_MAX_MESSAGE_LENGTH,
+        http: Default::default(),
+        transport: Default::default(),
+        quiet_hours: None,
+        exec: Default::default(),
+        recipients: Default::default(),
+        routes: Vec::new(),
+        config_file: None,
     }
 }
 
@@ -208,7 +223,11 @@ async fn post_text_mentions_specific_members() {
 
     with_client(routes, Some(vec![" user_a ".into()]), |client| async move {
         POST_INVOCATIONS.lock().await.clear();
-        client.post_text("任务完成").await.expect("post success");
+        let delivery = client.post_text("任务完成").await.expect("post success");
+        assert_eq!(delivery.recipient, "wxid_target@chatroom");
+        assert_eq!(delivery.contents, vec!["@显示A 任务完成".to_string()]);
+        assert_eq!(delivery.mentioned, vec!["user_a".to_string()]);
+        assert!(!delivery.at_all_fallback);
 
         let calls = POST_INVOCATIONS.lock().await.clone();
         assert_eq!(calls.len(), 1);
@@ -285,7 +304,12 @@ async fn post_text_handles_at_all_permission_denied() {
 
     with_client(routes.clone(), at_list.clone(), |client| async move {
         RETRY_INVOCATIONS.lock().await.clear();
-        client.post_text("测试").await.expect("post success");
+        let delivery = client.post_text("测试").await.expect("post success");
+        assert!(delivery.at_all_fallback);
+        assert!(delivery.mentioned.is_empty());
+        assert_eq!(delivery.contents, vec!["测试".to_string()]);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.messages[0].new_msg_id, "999");
 
         let calls = RETRY_INVOCATIONS.lock().await.clone();
         assert_eq!(calls.len(), 2);
@@ -407,3 +431,1356 @@ async fn post_text_member_lookup_failure_skips_mentions() {
     )
     .await;
 }
+
+#[tokio::test]
+async fn post_image_sends_image_url() {
+    static IMAGE_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    Lazy::force(&INIT_TRACING);
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postImage",
+        axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+            IMAGE_INVOCATIONS.lock().await.push(body.0.clone());
+            axum::Json(json!({
+                "ret": 200,
+                "msg": "操作成功",
+                "data": {
+                    "toWxid": "wxid_target@chatroom",
+                    "createTime": 1703841160,
+                    "msgId": 0,
+                    "newMsgId": 777,
+                    "type": 3
+                }
+            }))
+        }),
+    );
+
+    with_client(routes, None, |client| async move {
+        IMAGE_INVOCATIONS.lock().await.clear();
+        client
+            .post_image("https://example.com/chart.png")
+            .await
+            .expect("post image success");
+
+        let calls = IMAGE_INVOCATIONS.lock().await.clone();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["appId"], "wx_test_app");
+        assert_eq!(calls[0]["toWxid"], "wxid_target@chatroom");
+        assert_eq!(calls[0]["imgUrl"], "https://example.com/chart.png");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn post_image_propagates_api_failure() {
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postImage",
+        axum::routing::post(|_: axum::Json<serde_json::Value>| async move {
+            axum::Json(json!({
+                "ret": 500,
+                "msg": "操作失败",
+                "data": {"code": "-219"}
+            }))
+        }),
+    );
+
+    with_client(routes, None, |client| async move {
+        match client
+            .post_image("https://example.com/chart.png")
+            .await
+            .expect_err("api error")
+        {
+            GeweNoticeError::Business(ApiBusinessError::KnownError { code }) => {
+                assert_eq!(code, ApiErrorCode::NotInGroup);
+            }
+            other => panic!("unexpected error {other:?}"),
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn post_file_sends_name_and_url() {
+    static FILE_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    let routes = axum::Router::new()
+        .route(
+            "/files/build.log",
+            axum::routing::head(|| async move { [(axum::http::header::CONTENT_LENGTH, "2048")] }),
+        )
+        .route(
+            "/gewe/v2/api/message/postFile",
+            axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+                FILE_INVOCATIONS.lock().await.push(body.0.clone());
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {
+                        "toWxid": "wxid_target@chatroom",
+                        "createTime": 1703841160,
+                        "msgId": 0,
+                        "newMsgId": 666,
+                        "type": 6
+                    }
+                }))
+            }),
+        );
+
+    let server = MockServer::spawn(routes).await;
+    let client = GeweApiClient::new(base_config(server.address.clone(), None)).expect("client");
+    let file_url = format!("{}/files/build.log", server.address);
+
+    FILE_INVOCATIONS.lock().await.clear();
+    client
+        .post_file("build.log", &file_url)
+        .await
+        .expect("post file success");
+
+    let calls = FILE_INVOCATIONS.lock().await.clone();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0]["toWxid"], "wxid_target@chatroom");
+    assert_eq!(calls[0]["fileName"], "build.log");
+    assert_eq!(calls[0]["fileUrl"], file_url);
+}
+
+#[tokio::test]
+async fn post_file_rejects_unsupported_extension_before_request() {
+    static FILE_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postFile",
+        axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+            FILE_INVOCATIONS.lock().await.push(body.0.clone());
+            axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null}))
+        }),
+    );
+
+    with_client(routes, None, |client| async move {
+        FILE_INVOCATIONS.lock().await.clear();
+        match client
+            .post_file("payload.exe", "https://example.com/payload.exe")
+            .await
+            .expect_err("extension should be rejected")
+        {
+            GeweNoticeError::Attachment(AttachmentError::UnsupportedExtension {
+                extension,
+                ..
+            }) => assert_eq!(extension, "exe"),
+            other => panic!("unexpected error {other:?}"),
+        }
+
+        assert!(matches!(
+            client
+                .post_file("README", "https://example.com/README")
+                .await,
+            Err(GeweNoticeError::Attachment(
+                AttachmentError::MissingExtension { .. }
+            ))
+        ));
+
+        assert!(FILE_INVOCATIONS.lock().await.is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn post_file_rejects_oversized_file() {
+    static FILE_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    let routes = axum::Router::new()
+        .route(
+            "/files/huge.zip",
+            axum::routing::head(
+                || async move { [(axum::http::header::CONTENT_LENGTH, "1073741824")] },
+            ),
+        )
+        .route(
+            "/gewe/v2/api/message/postFile",
+            axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+                FILE_INVOCATIONS.lock().await.push(body.0.clone());
+                axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null}))
+            }),
+        );
+
+    let server = MockServer::spawn(routes).await;
+    let client = GeweApiClient::new(base_config(server.address.clone(), None)).expect("client");
+
+    FILE_INVOCATIONS.lock().await.clear();
+    match client
+        .post_file("huge.zip", &format!("{}/files/huge.zip", server.address))
+        .await
+        .expect_err("oversized file should be rejected")
+    {
+        GeweNoticeError::Attachment(AttachmentError::TooLarge { size, .. }) => {
+            assert_eq!(size, 1_073_741_824);
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+    assert!(FILE_INVOCATIONS.lock().await.is_empty());
+}
+
+#[tokio::test]
+async fn post_link_sends_card_then_mention_reminder() {
+    static LINK_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+    static TEXT_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    let success = || {
+        json!({
+            "ret": 200,
+            "msg": "操作成功",
+            "data": {
+                "toWxid": "wxid_target@chatroom",
+                "createTime": 1703841160,
+                "msgId": 0,
+                "newMsgId": 444,
+                "type": 5
+            }
+        })
+    };
+
+    let routes = axum::Router::new()
+        .route(
+            "/gewe/v2/api/group/getChatroomMemberList",
+            axum::routing::post(|_: axum::Json<serde_json::Value>| async move {
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {
+                        "memberList": [
+                            {"wxid": "user_a", "nickName": "A", "displayName": null}
+                        ],
+                        "chatroomOwner": null,
+                        "adminWxid": null
+                    }
+                }))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/message/postLink",
+            axum::routing::post(move |body: axum::Json<serde_json::Value>| async move {
+                LINK_INVOCATIONS.lock().await.push(body.0.clone());
+                axum::Json(success())
+            }),
+        )
+        .route(
+            "/gewe/v2/api/message/postText",
+            axum::routing::post(move |body: axum::Json<serde_json::Value>| async move {
+                TEXT_INVOCATIONS.lock().await.push(body.0.clone());
+                axum::Json(success())
+            }),
+        );
+
+    with_client(routes, Some(vec!["user_a".into()]), |client| async move {
+        LINK_INVOCATIONS.lock().await.clear();
+        TEXT_INVOCATIONS.lock().await.clear();
+
+        let link = LinkMessage {
+            title: "PR #42".into(),
+            desc: "Fix flaky test".into(),
+            link_url: "https://example.com/pr/42".into(),
+            thumb_url: "https://example.com/thumb.png".into(),
+        };
+        client.post_link(&link).await.expect("post link success");
+
+        let links = LINK_INVOCATIONS.lock().await.clone();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0]["title"], "PR #42");
+        assert_eq!(links[0]["desc"], "Fix flaky test");
+        assert_eq!(links[0]["linkUrl"], "https://example.com/pr/42");
+        assert_eq!(links[0]["thumbUrl"], "https://example.com/thumb.png");
+
+        let texts = TEXT_INVOCATIONS.lock().await.clone();
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0]["content"], "@A 请查看: PR #42");
+        assert_eq!(texts[0]["ats"], "user_a");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn post_link_reports_failed_reminder_as_warning() {
+    let routes = axum::Router::new()
+        .route(
+            "/gewe/v2/api/group/getChatroomMemberList",
+            axum::routing::post(|| async {
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {"memberList": [{"wxid": "user_a", "nickName": "A"}]}
+                }))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/message/postLink",
+            axum::routing::post(|| async {
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {"toWxid": "wxid_target@chatroom", "newMsgId": 555}
+                }))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/message/postText",
+            axum::routing::post(|| async {
+                axum::Json(json!({"ret": 500, "msg": "发送失败", "data": {"code": "-2"}}))
+            }),
+        );
+
+    with_client(routes, Some(vec!["user_a".into()]), |client| async move {
+        let link = LinkMessage {
+            title: "PR #43".into(),
+            desc: String::new(),
+            link_url: "https://example.com/pr/43".into(),
+            thumb_url: String::new(),
+        };
+        // 卡片已送达，提醒失败不能让调用方误以为需要重发卡片
+        let delivery = client.post_link(&link).await.expect("card delivered");
+        assert_eq!(delivery.messages.len(), 1);
+        assert_eq!(delivery.messages[0].new_msg_id, "555");
+        assert!(delivery.mentioned.is_empty());
+        let warning = delivery.warning.expect("reminder warning");
+        assert!(warning.contains("permission_denied"), "{warning}");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn post_text_to_overrides_recipient_without_default_mentions() {
+    static TEXT_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postText",
+        axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+            TEXT_INVOCATIONS.lock().await.push(body.0.clone());
+            axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null}))
+        }),
+    );
+
+    with_client(routes, Some(vec!["all".into()]), |client| async move {
+        TEXT_INVOCATIONS.lock().await.clear();
+        let recipient = Recipient {
+            wxid: WxId::new("wxid_oncall".into()).expect("valid wxid"),
+            at_list: None,
+        };
+        client
+            .post_text_to(&recipient, "请值班同学处理")
+            .await
+            .expect("post success");
+
+        let calls = TEXT_INVOCATIONS.lock().await.clone();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["toWxid"], "wxid_oncall");
+        assert_eq!(calls[0]["content"], "请值班同学处理");
+        assert!(calls[0].get("ats").is_none());
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn post_text_splits_long_content_and_mentions_first_part_only() {
+    static TEXT_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    let routes = axum::Router::new()
+        .route(
+            "/gewe/v2/api/group/getChatroomMemberList",
+            axum::routing::post(|_: axum::Json<serde_json::Value>| async move {
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {
+                        "memberList": [
+                            {"wxid": "user_a", "nickName": "A", "displayName": null}
+                        ]
+                    }
+                }))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/message/postText",
+            axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+                TEXT_INVOCATIONS.lock().await.push(body.0.clone());
+                axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null}))
+            }),
+        );
+
+    let server = MockServer::spawn(routes).await;
+    let mut config = base_config(server.address.clone(), Some(vec!["user_a".into()]));
+    config.max_message_length = 60;
+    let client = GeweApiClient::new(config).expect("client");
+
+    TEXT_INVOCATIONS.lock().await.clear();
+    let content = [
+        "第一行日志".repeat(8),
+        "第二行日志".repeat(8),
+        "完成".into(),
+    ]
+    .join("\n");
+    let delivery = client.post_text(&content).await.expect("post success");
+
+    let calls = TEXT_INVOCATIONS.lock().await.clone();
+    assert_eq!(delivery.parts, 2);
+    assert_eq!(calls.len(), 2);
+    assert_eq!(
+        calls[0]["content"],
+        format!("@A (1/2) {}", "第一行日志".repeat(8))
+    );
+    assert_eq!(calls[0]["ats"], "user_a");
+    assert_eq!(
+        calls[1]["content"],
+        format!("(2/2) {}\n完成", "第二行日志".repeat(8))
+    );
+    assert!(calls[1].get("ats").is_none());
+}
+
+#[tokio::test]
+async fn post_text_counts_mention_prefix_against_length_limit() {
+    static TEXT_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    let routes = axum::Router::new()
+        .route(
+            "/gewe/v2/api/group/getChatroomMemberList",
+            axum::routing::post(|_: axum::Json<serde_json::Value>| async move {
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {
+                        "memberList": [
+                            {"wxid": "user_a", "nickName": "值班负责人甲"},
+                            {"wxid": "user_b", "nickName": "值班负责人乙"},
+                            {"wxid": "user_c", "nickName": "值班负责人丙"}
+                        ]
+                    }
+                }))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/message/postText",
+            axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+                TEXT_INVOCATIONS.lock().await.push(body.0.clone());
+                axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null}))
+            }),
+        );
+
+    let server = MockServer::spawn(routes).await;
+    let mut config = base_config(
+        server.address.clone(),
+        Some(vec!["user_a".into(), "user_b".into(), "user_c".into()]),
+    );
+    config.max_message_length = 60;
+    let client = GeweApiClient::new(config).expect("client");
+
+    // 内容本身恰好等于上限，加上 @ 前缀后必须拆分
+    let content = "告".repeat(60);
+    let delivery = client.post_text(&content).await.expect("post success");
+
+    let calls = TEXT_INVOCATIONS.lock().await.clone();
+    assert_eq!(delivery.parts, 2);
+    assert_eq!(calls.len(), 2);
+    for call in &calls {
+        let sent = call["content"].as_str().unwrap();
+        assert!(sent.chars().count() <= 60, "{sent}");
+    }
+    assert!(calls[0]["content"]
+        .as_str()
+        .unwrap()
+        .starts_with("@值班负责人甲 @值班负责人乙 @值班负责人丙 (1/2) "));
+}
+
+#[tokio::test]
+async fn post_text_reports_failed_part() {
+    static TEXT_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postText",
+        axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+            let mut calls = TEXT_INVOCATIONS.lock().await;
+            calls.push(body.0.clone());
+            if calls.len() == 2 {
+                return axum::Json(json!({
+                    "ret": 500,
+                    "msg": "操作失败",
+                    "data": {"code": "-219"}
+                }));
+            }
+            axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null}))
+        }),
+    );
+
+    let server = MockServer::spawn(routes).await;
+    let mut config = base_config(server.address.clone(), None);
+    config.max_message_length = 60;
+    let client = GeweApiClient::new(config).expect("client");
+
+    TEXT_INVOCATIONS.lock().await.clear();
+    let content = ["a".repeat(45), "b".repeat(45), "c".repeat(45)].join("\n");
+    match client
+        .post_text(&content)
+        .await
+        .expect_err("second part fails")
+    {
+        GeweNoticeError::PartialDelivery {
+            part,
+            total,
+            source,
+        } => {
+            assert_eq!((part, total), (2, 3));
+            assert!(matches!(
+                *source,
+                GeweNoticeError::Business(ApiBusinessError::KnownError {
+                    code: ApiErrorCode::NotInGroup
+                })
+            ));
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+    assert_eq!(TEXT_INVOCATIONS.lock().await.len(), 2);
+}
+
+#[tokio::test]
+async fn partial_delivery_is_not_retryable() {
+    use gewe_notice_mcp::send;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postText",
+        axum::routing::post(move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) == 1 {
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                }
+                axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null}))
+            }
+        }),
+    );
+
+    let server = MockServer::spawn(routes).await;
+    let mut config = base_config(server.address.clone(), None);
+    config.max_message_length = 60;
+    config.http.request_timeout = Duration::from_millis(200);
+    config.http.retry.max_attempts = 1;
+    let client = GeweApiClient::new(config).expect("client");
+
+    let content = ["a".repeat(45), "b".repeat(45), "c".repeat(45)].join("\n");
+    let err = client
+        .post_text(&content)
+        .await
+        .expect_err("second part times out");
+    // 超时本身可以重试，但第一段已送达，整条重发会重复第一段
+    assert!(matches!(
+        err,
+        GeweNoticeError::PartialDelivery { part: 2, .. }
+    ));
+    assert!(!err.is_retryable());
+    assert_eq!(err.code(), "partial_delivery");
+    assert_eq!(err.retry_after(), None);
+    assert_eq!(send::exit_code(&err), send::EXIT_FAILURE);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn revoke_message_uses_ids_from_send_response() {
+    static REVOKE_INVOCATIONS: Lazy<Arc<Mutex<Vec<serde_json::Value>>>> =
+        Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+
+    let routes = axum::Router::new()
+        .route(
+            "/gewe/v2/api/message/postText",
+            axum::routing::post(|_: axum::Json<serde_json::Value>| async move {
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {
+                        "toWxid": "wxid_target@chatroom",
+                        "createTime": 1703841160,
+                        "msgId": 640355967,
+                        "newMsgId": 3768973957878705021_i64,
+                        "type": 1
+                    }
+                }))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/message/revokeMsg",
+            axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+                REVOKE_INVOCATIONS.lock().await.push(body.0.clone());
+                axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null}))
+            }),
+        );
+
+    with_client(routes, None, |client| async move {
+        REVOKE_INVOCATIONS.lock().await.clear();
+        let delivery = client.post_text("部署成功").await.expect("post success");
+        assert_eq!(delivery.messages.len(), 1);
+        assert_eq!(delivery.messages[0].new_msg_id, "3768973957878705021");
+
+        match client.revoke_message(Some("42")).await {
+            Err(GeweNoticeError::UnknownMessage(id)) => assert_eq!(id, "42"),
+            other => panic!("unexpected result {other:?}"),
+        }
+        assert!(REVOKE_INVOCATIONS.lock().await.is_empty());
+
+        let revoked = client.revoke_message(None).await.expect("revoke success");
+        assert_eq!(revoked.preview, "部署成功");
+
+        let calls = REVOKE_INVOCATIONS.lock().await.clone();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["toWxid"], "wxid_target@chatroom");
+        assert_eq!(calls[0]["msgId"], "640355967");
+        assert_eq!(calls[0]["newMsgId"], "3768973957878705021");
+        assert_eq!(calls[0]["createTime"], "1703841160");
+        assert!(client.sent_messages().is_empty());
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn delivery_counts_retried_attempts() {
+    static ATTEMPTS: Lazy<Arc<Mutex<u32>>> = Lazy::new(|| Arc::new(Mutex::new(0)));
+
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postImage",
+        axum::routing::post(|_: axum::Json<serde_json::Value>| async move {
+            let mut attempts = ATTEMPTS.lock().await;
+            *attempts += 1;
+            if *attempts == 1 {
+                // 第一次请求超时，触发重试
+                tokio::time::sleep(Duration::from_secs(2)).await;
+            }
+            axum::Json(json!({
+                "ret": 200,
+                "msg": "操作成功",
+                "data": {"msgId": 1, "newMsgId": 2, "createTime": 3}
+            }))
+        }),
+    );
+
+    let server = MockServer::spawn(routes).await;
+    let mut config = base_config(server.address.clone(), None);
+    config.http.request_timeout = Duration::from_secs(1);
+    let client = GeweApiClient::new(config).expect("create client");
+
+    {
+        *ATTEMPTS.lock().await = 0;
+        let delivery = client
+            .post_image("https://example.com/a.png")
+            .await
+            .expect("second attempt succeeds");
+
+        assert_eq!(delivery.attempts, 2);
+        assert!(delivery.elapsed_ms >= 1_000);
+        assert_eq!(
+            delivery.contents,
+            vec!["https://example.com/a.png".to_string()]
+        );
+        assert_eq!(delivery.messages[0].msg_id, "1");
+    }
+}
+
+#[tokio::test]
+async fn tls_failure_is_reported_with_detail() {
+    let routes = axum::Router::new();
+    let server = MockServer::spawn(routes).await;
+    // 以 https 访问明文 HTTP 服务，握手必然失败
+    let address = server.address.replace("http://", "https://");
+    let client = GeweApiClient::new(base_config(address, None)).expect("create client");
+
+    let err = client
+        .post_text("hello")
+        .await
+        .expect_err("handshake fails");
+    match &err {
+        GeweNoticeError::Network(NetworkError::TlsError { detail }) => {
+            assert!(detail.contains("TLS"), "{detail}");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+    assert_eq!(err.code(), "network_tls");
+    assert!(!err.is_retryable());
+    assert!(err.is_fatal());
+}
+
+#[tokio::test]
+async fn timeout_reports_configured_duration() {
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postText",
+        axum::routing::post(|_: axum::Json<serde_json::Value>| async move {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null}))
+        }),
+    );
+
+    let server = MockServer::spawn(routes).await;
+    let mut config = base_config(server.address.clone(), None);
+    config.http.request_timeout = Duration::from_millis(300);
+    config.http.retry.max_attempts = 1;
+    let client = GeweApiClient::new(config).expect("create client");
+
+    let err = client.post_text("hello").await.expect_err("times out");
+    assert!(
+        matches!(
+            err,
+            GeweNoticeError::Network(NetworkError::Timeout { duration })
+                if duration == Duration::from_millis(300)
+        ),
+        "{err:?}"
+    );
+}
+
+const ROTATED_TOKEN: &str = "11111111-1111-1111-1111-111111111111";
+
+fn token_guarded_post_text() -> axum::Router {
+    axum::Router::new().route(
+        "/gewe/v2/api/message/postText",
+        axum::routing::post(|headers: axum::http::HeaderMap| async move {
+            if headers["X-GEWE-TOKEN"] == ROTATED_TOKEN {
+                (
+                    StatusCode::OK,
+                    axum::Json(json!({"ret": 200, "msg": "操作成功", "data": null})),
+                )
+            } else {
+                (
+                    StatusCode::UNAUTHORIZED,
+                    axum::Json(json!({"ret": 401, "msg": "token无效", "data": null})),
+                )
+            }
+        }),
+    )
+}
+
+#[tokio::test]
+async fn unauthorized_response_rereads_token_file() {
+    let server = MockServer::spawn(token_guarded_post_text()).await;
+    let token_file = std::env::temp_dir().join(format!("gewe-token-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&token_file, ROTATED_TOKEN).unwrap();
+
+    let mut config = base_config(server.address.clone(), None);
+    config.token_source = TokenSource::File(token_file.clone());
+    let client = GeweApiClient::new(config).expect("create client");
+
+    client.post_text("hello").await.expect("token refreshed");
+    assert_eq!(client.config().token_str(), ROTATED_TOKEN);
+
+    std::fs::remove_file(&token_file).ok();
+}
+
+#[tokio::test]
+async fn unauthorized_inline_token_is_fatal() {
+    with_client(token_guarded_post_text(), None, |client| async move {
+        let err = client.post_text("hello").await.expect_err("stale token");
+        assert!(matches!(
+            err,
+            GeweNoticeError::Business(ApiBusinessError::Unauthorized)
+        ));
+        assert!(err.is_fatal());
+        assert_eq!(err.code(), "unauthorized");
+    })
+    .await;
+}
+
+/// 模拟一个可切换状态的 Gewe 地址：`healthy` 为 false 时所有请求返回 503。
+fn switchable_endpoint(
+    healthy: Arc<std::sync::atomic::AtomicBool>,
+    hits: Arc<std::sync::atomic::AtomicUsize>,
+) -> axum::Router {
+    use std::sync::atomic::Ordering;
+
+    let respond = move |data: serde_json::Value| {
+        let healthy = healthy.clone();
+        let hits = hits.clone();
+        move |_: axum::Json<serde_json::Value>| {
+            let healthy = healthy.load(Ordering::SeqCst);
+            let data = data.clone();
+            hits.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if healthy {
+                    (
+                        StatusCode::OK,
+                        axum::Json(json!({"ret": 200, "msg": "操作成功", "data": data})),
+                    )
+                } else {
+                    (
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        axum::Json(json!({"ret": 503, "msg": "unavailable"})),
+                    )
+                }
+            }
+        }
+    };
+
+    axum::Router::new()
+        .route(
+            "/gewe/v2/api/message/postText",
+            axum::routing::post(respond(json!({"newMsgId": 1}))),
+        )
+        .route(
+            "/gewe/v2/api/login/checkOnline",
+            axum::routing::post(respond(json!(true))),
+        )
+}
+
+#[tokio::test]
+async fn fails_over_to_standby_and_fails_back_after_probe() {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    let primary_healthy = Arc::new(AtomicBool::new(false));
+    let primary_hits = Arc::new(AtomicUsize::new(0));
+    let primary = MockServer::spawn(switchable_endpoint(
+        primary_healthy.clone(),
+        primary_hits.clone(),
+    ))
+    .await;
+    let standby = MockServer::spawn(switchable_endpoint(
+        Arc::new(AtomicBool::new(true)),
+        Arc::new(AtomicUsize::new(0)),
+    ))
+    .await;
+
+    let mut config = base_config(primary.address.clone(), None);
+    config.base_urls.push(standby.address.clone());
+    let client = GeweApiClient::new(config).expect("create client");
+
+    let delivery = client.post_text("first").await.expect("served by standby");
+    assert_eq!(delivery.endpoint, standby.address);
+    assert_eq!(delivery.attempts, 1);
+    assert_eq!(client.active_endpoint(), Some(standby.address.clone()));
+    assert_eq!(primary_hits.load(Ordering::SeqCst), 1);
+
+    // 记住健康的地址，后续请求不再先访问主地址
+    let delivery = client.post_text("second").await.expect("served by standby");
+    assert_eq!(delivery.endpoint, standby.address);
+    assert_eq!(primary_hits.load(Ordering::SeqCst), 1);
+
+    assert!(!client.probe_primary().await);
+    primary_healthy.store(true, Ordering::SeqCst);
+    assert!(client.probe_primary().await);
+    assert_eq!(client.active_endpoint(), None);
+
+    let delivery = client.post_text("third").await.expect("served by primary");
+    assert_eq!(delivery.endpoint, primary.address);
+}
+
+#[tokio::test]
+async fn connection_errors_fail_over_to_next_endpoint() {
+    let standby = MockServer::spawn(switchable_endpoint(
+        Arc::new(std::sync::atomic::AtomicBool::new(true)),
+        Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+    ))
+    .await;
+
+    let mut config = base_config("http://127.0.0.1:1".to_string(), None);
+    config.base_urls.push(standby.address.clone());
+    let client = GeweApiClient::new(config).expect("create client");
+
+    assert!(client.check_online().await.expect("standby online"));
+    assert_eq!(client.active_endpoint(), Some(standby.address.clone()));
+}
+
+#[tokio::test]
+async fn connect_timeouts_fail_over_to_next_endpoint() {
+    let standby = MockServer::spawn(switchable_endpoint(
+        Arc::new(std::sync::atomic::AtomicBool::new(true)),
+        Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+    ))
+    .await;
+
+    // 模拟丢包不应答的主机：监听队列已满，新的连接请求得不到应答
+    let socket = tokio::net::TcpSocket::new_v4().expect("socket");
+    socket.bind("127.0.0.1:0".parse().unwrap()).expect("bind");
+    let address = socket.local_addr().expect("address");
+    let listener = socket.listen(0).expect("listen");
+    let mut backlog = Vec::new();
+    for _ in 0..4 {
+        if let Ok(Ok(stream)) = tokio::time::timeout(
+            Duration::from_millis(200),
+            tokio::net::TcpStream::connect(address),
+        )
+        .await
+        {
+            backlog.push(stream);
+        }
+    }
+
+    let mut config = base_config(format!("http://{address}"), None);
+    config.base_urls.push(standby.address.clone());
+    config.http.request_timeout = Duration::from_secs(2);
+    config.http.send_timeout = Duration::from_secs(10);
+    let client = GeweApiClient::new(config).expect("create client");
+
+    let started = std::time::Instant::now();
+    assert!(client.check_online().await.expect("standby online"));
+    assert_eq!(client.active_endpoint(), Some(standby.address.clone()));
+    assert!(started.elapsed() < Duration::from_secs(2));
+    drop(listener);
+}
+
+#[test]
+fn empty_base_urls_are_rejected() {
+    let mut config = base_config("http://127.0.0.1:1".to_string(), None);
+    config.base_urls.clear();
+    match GeweApiClient::new(config.clone()) {
+        Err(err) => assert_eq!(err.code(), "config_invalid"),
+        Ok(_) => panic!("client without endpoints"),
+    }
+
+    let client = GeweApiClient::new(base_config("http://127.0.0.1:1".to_string(), None))
+        .expect("create client");
+    assert!(client.replace_config(config).is_err());
+    assert_eq!(client.config().base_urls, vec!["http://127.0.0.1:1"]);
+}
+
+fn doctor_routes() -> axum::Router {
+    axum::Router::new()
+        .route(
+            "/gewe/v2/api/login/checkOnline",
+            axum::routing::post(|| async {
+                axum::Json(json!({"ret": 200, "msg": "操作成功", "data": true}))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/group/getChatroomMemberList",
+            axum::routing::post(|| async {
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {
+                        "memberList": [
+                            {"wxid": "wxid_bot", "nickName": "机器人"},
+                            {"wxid": "wxid_alice", "nickName": "Alice"}
+                        ],
+                        "chatroomOwner": "wxid_alice",
+                        "adminWxid": ["wxid_bot"]
+                    }
+                }))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/personal/getProfile",
+            axum::routing::post(|| async {
+                axum::Json(json!({"ret": 200, "msg": "操作成功", "data": {"wxid": "wxid_bot"}}))
+            }),
+        )
+}
+
+/// 记录每次 `postText` 的消息内容并返回成功的路由。
+fn capturing_post_text() -> (axum::Router, Arc<Mutex<Vec<String>>>) {
+    let posted = Arc::new(Mutex::new(Vec::<String>::new()));
+    let captured = posted.clone();
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postText",
+        axum::routing::post(move |body: axum::Json<serde_json::Value>| {
+            let captured = captured.clone();
+            async move {
+                captured
+                    .lock()
+                    .await
+                    .push(body["content"].as_str().unwrap_or_default().to_string());
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {"toWxid": "12345@chatroom", "newMsgId": 1}
+                }))
+            }
+        }),
+    );
+    (routes, posted)
+}
+
+/// 清除运行环境中的 `GEWE_NOTICE_*` 变量，`RawConfig::parse_from` 会读取它们。
+/// 只在确实存在时修改环境，一般不会与其他测试同时读写环境变量。
+static CLEAR_ENV: Lazy<()> = Lazy::new(|| {
+    for (key, _) in std::env::vars_os() {
+        if key.to_string_lossy().starts_with("GEWE_NOTICE_") {
+            std::env::remove_var(key);
+        }
+    }
+});
+
+/// 与命令行子命令相同方式解析的配置，不受运行环境中配置变量的影响。
+fn cli_args(address: &str, at_list: &str) -> gewe_notice_mcp::config::RawConfig {
+    use clap::Parser;
+
+    Lazy::force(&CLEAR_ENV);
+    gewe_notice_mcp::config::RawConfig::parse_from([
+        "gewe-notice-mcp",
+        "--base-url",
+        address,
+        "--token",
+        "00000000-0000-0000-0000-000000000000",
+        "--app-id",
+        "wx_test_app",
+        "--wxid",
+        "12345@chatroom",
+        "--at-list",
+        at_list,
+    ])
+}
+
+#[tokio::test]
+async fn doctor_reports_mentions_outside_the_group() {
+    use gewe_notice_mcp::doctor::{self, CheckStatus};
+
+    let server = MockServer::spawn(doctor_routes()).await;
+
+    let report = doctor::run(cli_args(&server.address, "wxid_alice,wxid_ghost")).await;
+    assert!(report.has_failures(), "{report}");
+    let failed: Vec<_> = report
+        .checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .collect();
+    assert_eq!(failed.len(), 1, "{report}");
+    assert!(failed[0].detail.contains("wxid_ghost"), "{report}");
+    assert!(failed[0].hint.is_some());
+
+    let report = doctor::run(cli_args(&server.address, "all")).await;
+    assert!(!report.has_failures(), "{report}");
+    assert!(report
+        .checks
+        .iter()
+        .all(|check| check.status == CheckStatus::Pass));
+
+    // 与其他 wxid 混用时 all 不表示 @所有人，其余成员仍要检查
+    let report = doctor::run(cli_args(&server.address, "all,wxid_alice,wxid_ghost")).await;
+    let failed: Vec<_> = report
+        .checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .collect();
+    assert_eq!(failed.len(), 1, "{report}");
+    assert!(failed[0].detail.contains("wxid_ghost"), "{report}");
+    assert!(!failed[0].detail.contains("wxid_alice"), "{report}");
+    assert!(
+        failed[0].hint.as_deref().unwrap().contains("单独使用"),
+        "{report}"
+    );
+}
+
+#[tokio::test]
+async fn send_command_posts_text_and_maps_failures_to_exit_codes() {
+    use gewe_notice_mcp::send;
+
+    let routes = axum::Router::new().route(
+        "/gewe/v2/api/message/postText",
+        axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+            let ret = if body["content"] == "部署完成" {
+                200
+            } else {
+                500
+            };
+            axum::Json(json!({
+                "ret": ret,
+                "msg": "",
+                "data": {"code": "-219", "toWxid": "12345@chatroom", "newMsgId": 1}
+            }))
+        }),
+    );
+    let server = MockServer::spawn(routes).await;
+
+    let delivery = send::run(cli_args(&server.address, ""), "部署完成")
+        .await
+        .expect("sent");
+    assert_eq!(delivery.recipient, "12345@chatroom");
+    assert_eq!(delivery.parts, 1);
+
+    let err = send::run(cli_args(&server.address, ""), "部署失败")
+        .await
+        .expect_err("not in group");
+    assert_eq!(send::exit_code(&err), send::EXIT_FATAL);
+
+    let err = send::run(cli_args("http://127.0.0.1:1", ""), "部署完成")
+        .await
+        .expect_err("connection refused");
+    assert_eq!(send::exit_code(&err), send::EXIT_RETRYABLE);
+
+    let mut raw = cli_args(&server.address, "");
+    raw.wxid = None;
+    let err = send::run(raw, "部署完成").await.expect_err("missing wxid");
+    assert_eq!(send::exit_code(&err), send::EXIT_CONFIG);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn exec_command_notifies_with_tail_and_returns_child_exit_code() {
+    use gewe_notice_mcp::exec;
+
+    let (routes, posted) = capturing_post_text();
+    let server = MockServer::spawn(routes).await;
+
+    let mut raw = cli_args(&server.address, "");
+    raw.exec_failure_template = Some("{command} @ {hostname}: {status}\n{output}".into());
+    raw.exec_tail_lines = Some(2);
+    let argv: Vec<String> = [
+        "sh",
+        "-c",
+        "exec 2>&1; echo one; echo two; echo three >&2; exit 4",
+    ]
+    .map(String::from)
+    .to_vec();
+
+    assert_eq!(exec::run(raw, &argv).await, 4);
+    let posted = posted.lock().await;
+    assert_eq!(posted.len(), 1);
+    let content = &posted[0];
+    assert!(content.starts_with("sh -c 'exec 2>&1;"), "{content}");
+    assert!(content.contains(": 退出码 4\n"), "{content}");
+    assert!(!content.contains("one\n"), "{content}");
+    assert!(content.contains("three"), "{content}");
+}
+
+#[tokio::test]
+async fn contacts_are_listed_with_brief_info_and_filtered() {
+    use gewe_notice_mcp::contacts::{self, ContactFilter};
+    use gewe_notice_mcp::gewe_api::ContactKind;
+
+    let routes = axum::Router::new()
+        .route(
+            "/gewe/v2/api/contacts/fetchContactsList",
+            axum::routing::post(|| async {
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": {
+                        "friends": ["wxid_alice", "wxid_bob"],
+                        "chatrooms": ["34757816141@chatroom"],
+                        "ghs": ["gh_ignored"]
+                    }
+                }))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/contacts/getBriefInfo",
+            axum::routing::post(|body: axum::Json<serde_json::Value>| async move {
+                assert_eq!(body["wxids"].as_array().map(Vec::len), Some(3));
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": [
+                        {"userName": "wxid_alice", "nickName": "Alice", "remark": "运维 小A"},
+                        {"userName": "wxid_bob", "nickName": "Bob", "remark": ""},
+                        {"userName": "34757816141@chatroom", "nickName": "运维告警群"}
+                    ]
+                }))
+            }),
+        );
+    let server = MockServer::spawn(routes).await;
+
+    // 查询通讯录不需要预先知道接收者 wxid
+    let mut raw = cli_args(&server.address, "");
+    raw.wxid = None;
+    let all = contacts::run(raw.clone(), &ContactFilter::default())
+        .await
+        .expect("contacts");
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[0].remark.as_deref(), Some("运维 小A"));
+    assert_eq!(all[1].remark, None);
+    assert_eq!(all[2].kind, ContactKind::Chatroom);
+    assert_eq!(all[2].nickname.as_deref(), Some("运维告警群"));
+
+    let filter = ContactFilter {
+        kind: Some(ContactKind::Chatroom),
+        query: Some("运维".into()),
+    };
+    let groups = contacts::run(raw, &filter).await.expect("contacts");
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].wxid, "34757816141@chatroom");
+    assert!(contacts::render_table(&groups).contains("34757816141@chatroom"));
+}
+
+#[tokio::test]
+async fn contacts_are_cached_between_calls() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    let routes = axum::Router::new()
+        .route(
+            "/gewe/v2/api/contacts/fetchContactsList",
+            axum::routing::post(move || {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    axum::Json(json!({
+                        "ret": 200,
+                        "msg": "操作成功",
+                        "data": {"friends": ["wxid_alice"], "chatrooms": []}
+                    }))
+                }
+            }),
+        )
+        .route(
+            "/gewe/v2/api/contacts/getBriefInfo",
+            axum::routing::post(|| async {
+                axum::Json(json!({
+                    "ret": 200,
+                    "msg": "操作成功",
+                    "data": [{"userName": "wxid_alice", "nickName": "Alice"}]
+                }))
+            }),
+        );
+
+    with_client(routes, None, |client| async move {
+        let first = client.list_contacts().await.expect("contacts");
+        let second = client.list_contacts().await.expect("cached contacts");
+        assert_eq!(first, second);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn contacts_errors_keep_business_codes() {
+    let not_in_group = axum::Router::new().route(
+        "/gewe/v2/api/contacts/fetchContactsList",
+        axum::routing::post(|| async {
+            axum::Json(json!({"ret": 500, "msg": "获取失败", "data": {"code": "-219"}}))
+        }),
+    );
+    with_client(not_in_group, None, |client| async move {
+        let err = client.list_contacts().await.expect_err("known error");
+        assert!(matches!(
+            err,
+            GeweNoticeError::Business(ApiBusinessError::KnownError {
+                code: ApiErrorCode::NotInGroup
+            })
+        ));
+    })
+    .await;
+
+    // 未知错误时检查在线状态，离线仍报告为 bot_offline
+    let offline = axum::Router::new()
+        .route(
+            "/gewe/v2/api/contacts/fetchContactsList",
+            axum::routing::post(|| async {
+                axum::Json(json!({"ret": 500, "msg": "获取失败", "data": null}))
+            }),
+        )
+        .route(
+            "/gewe/v2/api/login/checkOnline",
+            axum::routing::post(|| async {
+                axum::Json(json!({"ret": 200, "msg": "操作成功", "data": false}))
+            }),
+        );
+    with_client(offline, None, |client| async move {
+        let err = client.list_contacts().await.expect_err("offline");
+        assert_eq!(err.code(), "bot_offline");
+    })
+    .await;
+
+    let unauthorized = axum::Router::new().route(
+        "/gewe/v2/api/contacts/fetchContactsList",
+        axum::routing::post(|| async {
+            axum::Json(json!({"ret": 401, "msg": "token 无效", "data": null}))
+        }),
+    );
+    with_client(unauthorized, None, |client| async move {
+        let err = client.list_contacts().await.expect_err("unauthorized");
+        assert_eq!(err.code(), "unauthorized");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn members_lists_typed_members_with_roles() {
+    use gewe_notice_mcp::members;
+
+    let server = MockServer::spawn(doctor_routes()).await;
+
+    let mut raw = cli_args(&server.address, "");
+    raw.wxid = None;
+    let list = members::run(raw, "12345").await.expect("members");
+    assert_eq!(list.len(), 2);
+    let bot = &list[0];
+    assert_eq!(bot.wxid, "wxid_bot");
+    assert_eq!(bot.name(), "机器人");
+    assert!(bot.is_admin && !bot.is_owner);
+    let alice = &list[1];
+    assert!(alice.is_owner && alice.can_mention_all());
+    assert_eq!(alice.display_name, None);
+
+    let json = serde_json::to_value(&list).expect("json");
+    assert_eq!(json[1]["nickname"], "Alice");
+    assert_eq!(json[1]["is_owner"], true);
+
+    let table = members::render_table(&list);
+    assert!(table.lines().nth(1).unwrap().contains("群主"), "{table}");
+}
+
+#[tokio::test]
+async fn watch_notifies_on_matching_log_lines() {
+    use gewe_notice_mcp::watch::{self, WatchOptions};
+    use std::io::Write;
+
+    let (routes, posted) = capturing_post_text();
+    let server = MockServer::spawn(routes).await;
+
+    let dir = std::env::temp_dir().join(format!("gewe-watch-it-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("temp dir");
+    let path = dir.join("job.log");
+    std::fs::write(&path, "FATAL before watching\n").expect("log file");
+
+    let options = WatchOptions {
+        file: path.clone(),
+        patterns: vec![regex::Regex::new("FATAL").unwrap()],
+        context: 1,
+        cooldown: Duration::from_secs(60),
+    };
+    let (ready_tx, ready) = tokio::sync::oneshot::channel();
+    let watcher = tokio::spawn(watch::run_with_ready(
+        cli_args(&server.address, ""),
+        options,
+        move || {
+            let _ = ready_tx.send(());
+        },
+    ));
+    // 等文件定位到末尾后再追加，否则追加的内容可能被当作已有内容跳过
+    tokio::time::timeout(Duration::from_secs(5), ready)
+        .await
+        .expect("watcher ready")
+        .expect("watcher started");
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .expect("open log");
+    writeln!(
+        file,
+        "step 1\nFATAL: worker crashed\nrestarting\nFATAL: worker crashed"
+    )
+    .unwrap();
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while posted.lock().await.is_empty() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(700)).await;
+    watcher.abort();
+    std::fs::remove_dir_all(&dir).ok();
+
+    let posted = posted.lock().await;
+    assert_eq!(
+        posted.len(),
+        1,
+        "cooldown suppresses the repeat: {posted:?}"
+    );
+    let content = &posted[0];
+    assert!(content.contains("job.log"), "{content}");
+    assert!(
+        content.contains("  step 1\n» FATAL: worker crashed\n  restarting"),
+        "{content}"
+    );
+    assert!(!content.contains("before watching"), "{content}");
+}